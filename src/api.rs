@@ -1,5 +1,8 @@
 //! the [`Api`] trait allows user define the tencentcloud an api
 //!
+//! with the `derive` feature, the `#[derive(TencentApi)]` implements the [`Api`] for the request
+//! struct, see the `tencentcloud-derive` crate
+//!
 //! ## Examples:
 //!
 //! ```rust
@@ -66,4 +69,26 @@ pub trait Api {
 
     /// the api host, for example: the tmt text translate is `tmt.tencentcloudapi.com`
     const HOST: &'static str;
+
+    /// the api scheme, default is `https`
+    const SCHEME: &'static str = "https";
+
+    /// the api path, default is `/`
+    const PATH: &'static str = "/";
+
+    /// whether the api is called without signing, for example: the sts
+    /// `AssumeRoleWithWebIdentity`, default is `false`
+    const SKIP_SIGN: bool = false;
+
+    /// the idempotency token field of the request, for example: `ClientToken`, default is `None`
+    ///
+    /// it marks the api as idempotent, all the `send` methods of the [`Client`](crate::Client)
+    /// generate a uuid v4 token once per call if the field of the request is not set or is the
+    /// empty string, and reuse it across the retries, so the retried create request never
+    /// creates the duplicate resource
+    ///
+    /// there is no separate `Idempotent` marker trait: the marker would need the field name as
+    /// well, and the `send` methods are generic over [`Api`] only, so this const is the single
+    /// opt-in which the client can see without the specialization
+    const IDEMPOTENCY_TOKEN_FIELD: Option<&'static str> = None;
 }
@@ -1,16 +1,16 @@
+use std::io;
+
+use async_std::net::{TcpStream, ToSocketAddrs};
 use async_std::prelude::*;
 use async_std::task;
+use tokio_util::compat::FuturesAsyncReadCompatExt;
 
-#[cfg(all(
-    feature = "async-std-native-tls",
-    not(feature = "async-std-rustls-tls")
-))]
-pub use self::native_tls_compat::{Connector, MaybeTls};
-#[cfg(all(
-    feature = "async-std-rustls-tls",
-    not(feature = "async-std-native-tls")
-))]
-pub use self::rustls_compat::{Connector, MaybeTls};
+use crate::proxy::{tunnel, Proxy};
+
+#[cfg(feature = "async-std-native-tls")]
+pub use self::native_tls_compat::Connector as NativeTlsConnector;
+#[cfg(feature = "async-std-rustls-tls")]
+pub use self::rustls_compat::Connector as RustlsConnector;
 
 #[derive(Clone, Default)]
 pub struct HyperExecutor;
@@ -25,31 +25,57 @@ where
     }
 }
 
+/// connect the `host`:`port` directly, or through the `proxy`
+async fn connect(host: &str, port: u16, proxy: Option<&Proxy>) -> io::Result<TcpStream> {
+    let stream = tunnel::connect(
+        proxy,
+        host,
+        port,
+        |host, port| async move { Ok(TcpStream::connect((host.as_str(), port)).await?.compat()) },
+        |host, port| async move {
+            match (host.as_str(), port).to_socket_addrs().await?.next() {
+                None => Err(io::Error::other(format!("{host} is not resolved"))),
+                Some(addr) => Ok(addr.ip()),
+            }
+        },
+    )
+    .await?;
+
+    Ok(stream.into_inner())
+}
+
 #[cfg(feature = "async-std-rustls-tls")]
 mod rustls_compat {
     use std::future::{ready, Ready};
     use std::io;
-    use std::io::ErrorKind;
     use std::pin::Pin;
     use std::sync::Arc;
     use std::task::{Context, Poll};
+    use std::time::Duration;
 
     use async_std::net::TcpStream;
     use futures_rustls::client::TlsStream;
-    use futures_rustls::rustls::{Certificate, ClientConfig, RootCertStore, ServerName};
+    use futures_rustls::pki_types::ServerName;
     use futures_rustls::TlsConnector;
     use futures_util::future::{BoxFuture, Either};
-    use futures_util::{FutureExt, TryFutureExt};
-    use hyper::client::connect::{Connected, Connection};
-    use hyper::service::Service;
+    use futures_util::FutureExt;
     use hyper::Uri;
+    use hyper_util::client::legacy::connect::{Connected, Connection};
+    use hyper_util::rt::TokioIo;
     use tokio::io::ReadBuf;
     use tokio_util::compat::{Compat, FuturesAsyncReadCompatExt};
+    use tower_service::Service;
+
+    use super::connect;
+    use crate::error::Error;
+    use crate::proxy::Proxy;
+    use crate::timer;
+    use crate::transport::TransportConfig;
 
     #[derive(Debug)]
     pub enum MaybeTls {
         Tcp(Compat<TcpStream>),
-        Tls(Compat<TlsStream<TcpStream>>),
+        Tls(Box<Compat<TlsStream<TcpStream>>>),
     }
 
     impl tokio::io::AsyncRead for MaybeTls {
@@ -102,42 +128,45 @@ mod rustls_compat {
 
     impl Connection for MaybeTls {
         fn connected(&self) -> Connected {
-            Connected::new()
+            match self {
+                MaybeTls::Tcp(_) => Connected::new(),
+                MaybeTls::Tls(tls) => {
+                    let (_, client_connection) = tls.get_ref().get_ref();
+                    match client_connection.alpn_protocol() {
+                        Some(alpn) if alpn == b"h2" => Connected::new().negotiated_h2(),
+                        _ => Connected::new(),
+                    }
+                }
+            }
         }
     }
 
     #[derive(Clone)]
     pub struct Connector {
         tls_connector: TlsConnector,
+        connect_timeout: Option<Duration>,
+        proxy: Option<Proxy>,
     }
 
-    impl Default for Connector {
-        fn default() -> Self {
-            let certs = rustls_native_certs::load_native_certs()
-                .unwrap_or_else(|err| panic!("load native certs failed: {err}"));
-            let mut root_cert_store = RootCertStore::empty();
-            for cert in certs {
-                root_cert_store
-                    .add(&Certificate(cert.0))
-                    .unwrap_or_else(|err| panic!("add root cert failed: {err}"));
-            }
-
-            let mut client_config = ClientConfig::builder()
-                .with_safe_defaults()
-                .with_root_certificates(root_cert_store)
-                .with_no_client_auth();
-
-            // enable http1 and http2
-            client_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    impl Connector {
+        pub fn new(config: &TransportConfig) -> Result<Self, Error> {
+            let mut client_config = config.tls.rustls_client_config()?;
+            client_config.alpn_protocols = config
+                .alpn_protocols()
+                .iter()
+                .map(|alpn| alpn.as_bytes().to_vec())
+                .collect();
 
-            Self {
+            Ok(Self {
                 tls_connector: Arc::new(client_config).into(),
-            }
+                connect_timeout: config.connect_timeout,
+                proxy: config.proxy.clone(),
+            })
         }
     }
 
     impl Service<Uri> for Connector {
-        type Response = MaybeTls;
+        type Response = TokioIo<MaybeTls>;
         type Error = io::Error;
         type Future = Either<
             BoxFuture<'static, Result<Self::Response, Self::Error>>,
@@ -151,28 +180,32 @@ mod rustls_compat {
         fn call(&mut self, req: Uri) -> Self::Future {
             let scheme = match req.scheme_str() {
                 None => {
-                    return ready(Err(io::Error::new(ErrorKind::Other, "miss scheme")))
-                        .right_future();
+                    return ready(Err(io::Error::other("miss scheme"))).right_future();
                 }
                 Some(scheme) => scheme,
             };
             let host = match req.host() {
                 None => {
-                    return ready(Err(io::Error::new(ErrorKind::Other, "miss host")))
-                        .right_future();
+                    return ready(Err(io::Error::other("miss host"))).right_future();
                 }
                 Some(host) => host,
             };
 
+            let proxy = self.proxy.clone();
+
             match scheme {
                 "http" => {
                     let port = req.port_u16().unwrap_or(80);
                     let host = host.to_string();
+                    let connect_timeout = self.connect_timeout;
 
                     async move {
-                        TcpStream::connect((host.as_str(), port))
-                            .map_ok(|stream| MaybeTls::Tcp(stream.compat()))
-                            .await
+                        timer::maybe_timeout(connect_timeout, async {
+                            let tcp_stream = connect(&host, port, proxy.as_ref()).await?;
+
+                            Ok(TokioIo::new(MaybeTls::Tcp(tcp_stream.compat())))
+                        })
+                        .await?
                     }
                     .boxed()
                     .left_future()
@@ -181,29 +214,32 @@ mod rustls_compat {
                 "https" => {
                     let port = req.port_u16().unwrap_or(443);
                     let tls_connector = self.tls_connector.clone();
-                    let server_name = match ServerName::try_from(host) {
-                        Err(err) => {
-                            return ready(Err(io::Error::new(ErrorKind::Other, err))).right_future()
-                        }
+                    // the ipv6 host of the uri has the brackets, the server name is the ip
+                    let host = host.trim_start_matches('[').trim_end_matches(']');
+                    let server_name = match ServerName::try_from(host.to_string()) {
+                        Err(err) => return ready(Err(io::Error::other(err))).right_future(),
                         Ok(server_name) => server_name,
                     };
                     let host = host.to_string();
+                    let connect_timeout = self.connect_timeout;
 
+                    // the timeout covers both tcp connect and tls handshake
                     async move {
-                        let tcp_stream = TcpStream::connect((host.as_str(), port)).await?;
-                        let tls_stream = tls_connector.connect(server_name, tcp_stream).await?;
+                        timer::maybe_timeout(connect_timeout, async {
+                            let tcp_stream = connect(&host, port, proxy.as_ref()).await?;
+                            let tls_stream = tls_connector.connect(server_name, tcp_stream).await?;
 
-                        Ok(MaybeTls::Tls(tls_stream.compat()))
+                            Ok(TokioIo::new(MaybeTls::Tls(Box::new(tls_stream.compat()))))
+                        })
+                        .await?
                     }
                     .boxed()
                     .left_future()
                 }
 
-                scheme => ready(Err(io::Error::new(
-                    ErrorKind::Other,
-                    format!("invalid scheme: {scheme}"),
-                )))
-                .right_future(),
+                scheme => {
+                    ready(Err(io::Error::other(format!("invalid scheme: {scheme}")))).right_future()
+                }
             }
         }
     }
@@ -213,24 +249,31 @@ mod rustls_compat {
 mod native_tls_compat {
     use std::future::{ready, Ready};
     use std::io;
-    use std::io::ErrorKind;
     use std::pin::Pin;
     use std::task::{Context, Poll};
+    use std::time::Duration;
 
-    use async_native_tls::{TlsConnector, TlsStream};
     use async_std::net::TcpStream;
     use futures_util::future::{BoxFuture, Either};
-    use futures_util::{FutureExt, TryFutureExt};
-    use hyper::client::connect::{Connected, Connection};
-    use hyper::service::Service;
+    use futures_util::FutureExt;
     use hyper::Uri;
+    use hyper_util::client::legacy::connect::{Connected, Connection};
+    use hyper_util::rt::TokioIo;
     use tokio::io::ReadBuf;
+    use tokio_native_tls::{TlsConnector, TlsStream};
     use tokio_util::compat::{Compat, FuturesAsyncReadCompatExt};
+    use tower_service::Service;
+
+    use super::connect;
+    use crate::error::Error;
+    use crate::proxy::Proxy;
+    use crate::timer;
+    use crate::transport::TransportConfig;
 
     #[derive(Debug)]
     pub enum MaybeTls {
         Tcp(Compat<TcpStream>),
-        Tls(Compat<TlsStream<TcpStream>>),
+        Tls(TlsStream<Compat<TcpStream>>),
     }
 
     impl tokio::io::AsyncRead for MaybeTls {
@@ -283,17 +326,37 @@ mod native_tls_compat {
 
     impl Connection for MaybeTls {
         fn connected(&self) -> Connected {
-            Connected::new()
+            match self {
+                MaybeTls::Tcp(_) => Connected::new(),
+                MaybeTls::Tls(tls) => match tls.get_ref().negotiated_alpn() {
+                    Ok(Some(alpn)) if alpn == b"h2" => Connected::new().negotiated_h2(),
+                    _ => Connected::new(),
+                },
+            }
         }
     }
 
-    #[derive(Default, Clone)]
+    #[derive(Clone)]
     pub struct Connector {
-        _priv: (),
+        tls_connector: TlsConnector,
+        connect_timeout: Option<Duration>,
+        proxy: Option<Proxy>,
+    }
+
+    impl Connector {
+        pub fn new(config: &TransportConfig) -> Result<Self, Error> {
+            let tls_connector = config.tls.native_tls_connector(config.alpn_protocols())?;
+
+            Ok(Self {
+                tls_connector: tls_connector.into(),
+                connect_timeout: config.connect_timeout,
+                proxy: config.proxy.clone(),
+            })
+        }
     }
 
     impl Service<Uri> for Connector {
-        type Response = MaybeTls;
+        type Response = TokioIo<MaybeTls>;
         type Error = io::Error;
         type Future = Either<
             BoxFuture<'static, Result<Self::Response, Self::Error>>,
@@ -307,28 +370,32 @@ mod native_tls_compat {
         fn call(&mut self, req: Uri) -> Self::Future {
             let scheme = match req.scheme_str() {
                 None => {
-                    return ready(Err(io::Error::new(ErrorKind::Other, "miss scheme")))
-                        .right_future();
+                    return ready(Err(io::Error::other("miss scheme"))).right_future();
                 }
                 Some(scheme) => scheme,
             };
             let host = match req.host() {
                 None => {
-                    return ready(Err(io::Error::new(ErrorKind::Other, "miss host")))
-                        .right_future();
+                    return ready(Err(io::Error::other("miss host"))).right_future();
                 }
                 Some(host) => host,
             };
 
+            let proxy = self.proxy.clone();
+
             match scheme {
                 "http" => {
                     let port = req.port_u16().unwrap_or(80);
                     let host = host.to_string();
+                    let connect_timeout = self.connect_timeout;
 
                     async move {
-                        TcpStream::connect((host.as_str(), port))
-                            .map_ok(|stream| MaybeTls::Tcp(stream.compat()))
-                            .await
+                        timer::maybe_timeout(connect_timeout, async {
+                            let tcp_stream = connect(&host, port, proxy.as_ref()).await?;
+
+                            Ok(TokioIo::new(MaybeTls::Tcp(tcp_stream.compat())))
+                        })
+                        .await?
                     }
                     .boxed()
                     .left_future()
@@ -336,27 +403,34 @@ mod native_tls_compat {
 
                 "https" => {
                     let port = req.port_u16().unwrap_or(443);
-                    let host = host.to_string();
-                    let tls_connector = TlsConnector::new();
+                    // the ipv6 host of the uri has the brackets, the tls domain is the ip
+                    let host = host
+                        .trim_start_matches('[')
+                        .trim_end_matches(']')
+                        .to_string();
+                    let tls_connector = self.tls_connector.clone();
+                    let connect_timeout = self.connect_timeout;
 
+                    // the timeout covers both tcp connect and tls handshake
                     async move {
-                        let tcp_stream = TcpStream::connect((host.as_str(), port)).await?;
-                        let tls_stream = tls_connector
-                            .connect(host, tcp_stream)
-                            .await
-                            .map_err(|err| io::Error::new(ErrorKind::Other, err))?;
-
-                        Ok(MaybeTls::Tls(tls_stream.compat()))
+                        timer::maybe_timeout(connect_timeout, async {
+                            let tcp_stream = connect(&host, port, proxy.as_ref()).await?;
+                            let tls_stream = tls_connector
+                                .connect(&host, tcp_stream.compat())
+                                .await
+                                .map_err(io::Error::other)?;
+
+                            Ok(TokioIo::new(MaybeTls::Tls(tls_stream)))
+                        })
+                        .await?
                     }
                     .boxed()
                     .left_future()
                 }
 
-                scheme => ready(Err(io::Error::new(
-                    ErrorKind::Other,
-                    format!("invalid scheme: {scheme}"),
-                )))
-                .right_future(),
+                scheme => {
+                    ready(Err(io::Error::other(format!("invalid scheme: {scheme}")))).right_future()
+                }
             }
         }
     }
@@ -1,17 +1,37 @@
 //! tencentcloud api client
 
 use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 
-use http_body::Limited;
-use hyper::{body, Body, Method, Request, StatusCode};
-use serde::Deserialize;
+use futures_util::future::{poll_fn, BoxFuture};
+use futures_util::FutureExt;
+use hyper::body::Bytes;
+use hyper::header::{HeaderName, HeaderValue};
+use hyper::{HeaderMap, StatusCode, Version};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
 use time::OffsetDateTime;
+use tower_layer::Layer;
+use tower_service::Service;
 use tracing::{instrument, trace};
 
 use crate::api::Api;
-use crate::error::{ApiError, Error};
-use crate::http_client::{new_http_client, HttpClient};
-use crate::tc3_hmac;
+use crate::credential::{CredentialProvider, ProfileProvider};
+use crate::endpoint::{DefaultEndpointResolver, Endpoint, EndpointResolver};
+use crate::error::Error;
+use crate::http_client::new_default_transport;
+use crate::idempotent;
+use crate::proxy::Proxy;
+use crate::retry::RetryPolicy;
+use crate::service::{
+    ApiRequest, ApiResponse, RateLimit, RateLimitLayer, Retry, RetryLayer, SignedRequest, Signing,
+    SigningLayer, Timeout, TimeoutLayer, Trace, TraceLayer, TransportService,
+};
+use crate::timer;
+use crate::tls::TlsConfig;
+use crate::transport::{ResponseBody, Transport, TransportConfig};
 
 /// tencentcloud api client
 ///
@@ -19,112 +39,739 @@ use crate::tc3_hmac;
 #[derive(Debug, Clone)]
 pub struct Client {
     region: String,
-    http_client: HttpClient,
-    auth: Auth,
     response_size_limit: Option<usize>,
+    endpoint_resolver: Arc<dyn EndpointResolver>,
+    total_timeout: Option<Duration>,
+    language: Option<Language>,
+    signing: SigningLayer,
+    transport: TransportService,
+    service: Pipeline,
 }
 
+/// the pipeline of the [`Client`], see the [`service`](crate::service) module
+type Pipeline = Trace<Retry<RateLimit<Timeout<Signing<TransportService>>>>>;
+
 impl Client {
     /// create an api client
     ///
     /// `response_size_limit` is used to limit the http response size, if `response_size_limit` is
     /// set and response body size is bigger then `response_size_limit`, will return error
+    ///
+    /// use [`Client::builder`] if more options are needed
+    ///
+    /// # Panics
+    ///
+    /// panics if the default transport can't be created, for example, the native root
+    /// certificates can't be loaded, use [`Client::try_new`] to handle the error
     pub fn new(region: String, auth: Auth, response_size_limit: impl Into<Option<usize>>) -> Self {
-        Self {
-            region,
-            http_client: new_http_client(),
-            auth,
-            response_size_limit: response_size_limit.into(),
-        }
+        Self::try_new(region, auth, response_size_limit)
+            .unwrap_or_else(|err| panic!("create client failed: {err}"))
+    }
+
+    /// create an api client, return the error if the default transport can't be created
+    ///
+    /// see [`Client::new`] for the arguments
+    pub fn try_new(
+        region: String,
+        auth: Auth,
+        response_size_limit: impl Into<Option<usize>>,
+    ) -> Result<Self, Error> {
+        let mut builder = Self::builder().region(region);
+        builder.response_size_limit = response_size_limit.into();
+
+        builder.finish(Arc::new(auth))
+    }
+
+    /// create a [`ClientBuilder`] to configure the [`Client`]
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::default()
+    }
+
+    /// set the [`EndpointResolver`], the default is [`DefaultEndpointResolver`] which uses the
+    /// [`Api::HOST`]
+    pub fn with_endpoint_resolver(
+        mut self,
+        endpoint_resolver: impl EndpointResolver + 'static,
+    ) -> Self {
+        self.endpoint_resolver = Arc::new(endpoint_resolver);
+
+        self
     }
 
     /// send api request, get the api response and request id
     ///
     /// the `request` and `response` types are defined by the `A`: [`Api`]
-    #[instrument(level = "trace", err)]
+    ///
+    /// the transient errors are retried by the [`RetryPolicy`]
+    ///
+    /// use [`Client::send_with_meta`] if the other response metadata is needed
     pub async fn send<A: Api>(&self, request: &A::Request) -> Result<(A::Response, String), Error> {
-        let payload = serde_json::to_vec(request)?;
+        let (resp, meta) = self.send_with_meta::<A>(request).await?;
 
-        trace!("marshal request done");
+        Ok((resp, meta.request_id))
+    }
 
-        let request = self.create_request::<A>(payload)?;
+    /// send api request, get the api response and the [`ResponseMeta`]
+    ///
+    /// the `request` and `response` types are defined by the `A`: [`Api`]
+    ///
+    /// the transient errors are retried by the [`RetryPolicy`]
+    #[instrument(level = "trace", err)]
+    pub async fn send_with_meta<A: Api>(
+        &self,
+        request: &A::Request,
+    ) -> Result<(A::Response, ResponseMeta), Error> {
+        let payload = idempotent::marshal(request, A::IDEMPOTENCY_TOKEN_FIELD, A::ACTION)?;
 
-        trace!(?request, "create http request done");
+        self.send_target(self.target::<A>(None, payload)?).await
+    }
 
-        let response = self.http_client.request(request).await?;
+    /// send api request with the [`RequestOptions`], get the api response and request id
+    ///
+    /// the `options` override the client settings for this call only
+    #[instrument(level = "trace", err)]
+    pub async fn send_with<A: Api>(
+        &self,
+        request: &A::Request,
+        options: &RequestOptions,
+    ) -> Result<(A::Response, String), Error> {
+        let payload = idempotent::marshal(request, A::IDEMPOTENCY_TOKEN_FIELD, A::ACTION)?;
 
-        trace!(?response, "get http response done");
+        let (resp, meta) = self
+            .send_target(self.target::<A>(Some(options), payload)?)
+            .await?;
 
-        if response.status() != StatusCode::OK {
-            return Err(Error::Other(
-                format!("status code is not OK: {}", response.status()).into(),
-            ));
+        Ok((resp, meta.request_id))
+    }
+
+    /// create the [`ApiRequest`] of the `A`: [`Api`] with the `options`, it is sent by the
+    /// [`Client`] as the tower service
+    ///
+    /// the idempotency token is set like [`Client::send`]
+    pub fn api_request<A: Api>(
+        &self,
+        request: &A::Request,
+        options: &RequestOptions,
+    ) -> Result<ApiRequest, Error> {
+        let payload = idempotent::marshal(request, A::IDEMPOTENCY_TOKEN_FIELD, A::ACTION)?;
+
+        self.target::<A>(Some(options), payload)
+    }
+
+    /// the [`ApiRequest`] of the `A`: [`Api`] with the marshaled `payload`
+    pub(crate) fn target<A: Api>(
+        &self,
+        options: Option<&RequestOptions>,
+        payload: Vec<u8>,
+    ) -> Result<ApiRequest, Error> {
+        let default_endpoint = Endpoint {
+            scheme: A::SCHEME.to_string(),
+            host: A::HOST.to_string(),
+            path: A::PATH.to_string(),
+        };
+
+        let mut target = self.new_target(
+            A::SERVICE,
+            A::VERSION,
+            A::ACTION,
+            default_endpoint,
+            A::SKIP_SIGN,
+            options,
+        )?;
+        target.payload = payload.into();
+
+        Ok(target)
+    }
+
+    /// the [`ApiRequest`] without the payload
+    fn new_target(
+        &self,
+        service: &str,
+        version: &str,
+        action: &str,
+        default_endpoint: Endpoint,
+        skip_sign: bool,
+        options: Option<&RequestOptions>,
+    ) -> Result<ApiRequest, Error> {
+        let mut target = ApiRequest {
+            service: service.to_string(),
+            version: version.to_string(),
+            action: action.to_string(),
+            region: self.region.clone(),
+            endpoint: default_endpoint,
+            skip_sign,
+            language: self.language,
+            headers: HeaderMap::new(),
+            payload: Bytes::new(),
+            total_timeout: self.total_timeout,
+            response_size_limit: self.response_size_limit,
+        };
+
+        if let Some(options) = options {
+            if let Some(region) = &options.region {
+                target.region = region.clone();
+            }
+            if let Some(language) = options.language {
+                target.language = Some(language);
+            }
+            if let Some(limit) = options.response_size_limit {
+                target.response_size_limit = Some(limit);
+            }
+            if let Some(deadline) = options.deadline {
+                let remaining = deadline
+                    .checked_duration_since(Instant::now())
+                    .ok_or(Error::Timeout)?;
+                target.total_timeout = Some(match target.total_timeout {
+                    None => remaining,
+                    Some(total_timeout) => total_timeout.min(remaining),
+                });
+            }
+
+            if let Some(name) = options.headers.keys().find(|name| {
+                !name.as_str().starts_with("x-tc-") || RESERVED_HEADERS.contains(&name.as_str())
+            }) {
+                return Err(Error::Other(
+                    format!("header {name} can't be set by the request options").into(),
+                ));
+            }
+            target.headers = options.headers.clone();
         }
 
-        let body = response.into_body();
-        let body = match self.response_size_limit {
-            None => body::to_bytes(body).await?,
+        target.endpoint = self.resolve_endpoint(service, &target.region, target.endpoint);
 
-            Some(limit) => body::to_bytes(Limited::new(body, limit))
-                .await
-                .map_err(|err| Error::Other(err))?,
+        Ok(target)
+    }
+
+    /// call the api by the names, get the `Response` object of the api response, the request id
+    /// is kept in its `RequestId` field
+    ///
+    /// it is same as [`Client::send`] but the api is decided at runtime, the `host` is resolved
+    /// by the [`EndpointResolver`] like the [`Api::HOST`], the empty `region` means the api is
+    /// not regional
+    #[instrument(level = "trace", skip(request), err)]
+    pub async fn call_raw(
+        &self,
+        service: &str,
+        version: &str,
+        action: &str,
+        host: &str,
+        region: &str,
+        request: Value,
+    ) -> Result<Value, Error> {
+        let payload = serde_json::to_vec(&request)?;
+
+        trace!("marshal request done");
+
+        let default_endpoint = Endpoint {
+            scheme: "https".to_string(),
+            host: host.to_string(),
+            path: "/".to_string(),
         };
+        let options = RequestOptions::new().with_region(region);
+        let mut target = self.new_target(
+            service,
+            version,
+            action,
+            default_endpoint,
+            false,
+            Some(&options),
+        )?;
+        target.payload = payload.into();
+
+        let (mut resp, meta) = self.send_target::<Value>(target).await?;
+        if let Value::Object(resp) = &mut resp {
+            resp.insert("RequestId".to_string(), Value::String(meta.request_id));
+        }
+
+        Ok(resp)
+    }
+
+    /// send the [`ApiRequest`] by the pipeline of the client, the total timeout covers all the
+    /// attempts
+    pub(crate) async fn call_api(&self, request: ApiRequest) -> Result<ApiResponse, Error> {
+        let start = Instant::now();
+        let total_timeout = request.total_timeout;
+        let mut service = self.service.clone();
+        let mut response = timer::maybe_timeout(total_timeout, async {
+            poll_fn(|cx| service.poll_ready(cx)).await?;
+
+            service.call(request).await
+        })
+        .await
+        .map_err(|_| Error::Timeout)??;
+        response.meta.latency = start.elapsed();
+
+        Ok(response)
+    }
+
+    pub(crate) async fn send_target<T: DeserializeOwned>(
+        &self,
+        request: ApiRequest,
+    ) -> Result<(T, ResponseMeta), Error> {
+        self.call_api(request).await?.deserialize()
+    }
+
+    /// resolve the endpoint of the `service`
+    ///
+    /// the returned [`Endpoint`] is used both to build the request uri and to sign the request
+    fn resolve_endpoint(&self, service: &str, region: &str, default: Endpoint) -> Endpoint {
+        self.endpoint_resolver.resolve(service, region, &default)
+    }
+}
+
+/// the [`Client`] as the tower service of the [`ApiRequest`], the request is created by the
+/// [`Client::api_request`]
+impl Service<ApiRequest> for Client {
+    type Response = ApiResponse;
+    type Error = Error;
+    type Future = BoxFuture<'static, Result<ApiResponse, Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: ApiRequest) -> Self::Future {
+        let client = self.clone();
+
+        async move { client.call_api(request).await }.boxed()
+    }
+}
 
-        trace!("read http body done");
+impl Client {
+    /// sign the [`ApiRequest`] into the [`SignedRequest`] by the credential of the client
+    ///
+    /// the signed request is sent by the [`Client`] as the tower service of the
+    /// [`SignedRequest`], so the application middlewares can run between the signing and the
+    /// sending
+    pub async fn sign(&self, request: &ApiRequest) -> Result<SignedRequest, Error> {
+        self.signing.create_request(request).await
+    }
+}
+
+/// the [`Client`] as the tower service of the [`SignedRequest`], the request is created by the
+/// [`Client::sign`] and sent by the transport of the client, the response is not checked
+///
+/// the retry, timeout and rate limit of the client only apply to the [`ApiRequest`]
+impl Service<SignedRequest> for Client {
+    type Response = hyper::Response<ResponseBody>;
+    type Error = Error;
+    type Future = BoxFuture<'static, Result<hyper::Response<ResponseBody>, Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.transport.poll_ready(cx)
+    }
 
-        let response = serde_json::from_slice::<Response<A::Response>>(&body)?;
+    fn call(&mut self, request: SignedRequest) -> Self::Future {
+        self.transport.call(request)
+    }
+}
 
-        trace!(?response, "unmarshal response done");
+/// the headers which are set by the client, they can't be set by the [`RequestOptions`]
+const RESERVED_HEADERS: &[&str] = &[
+    "x-tc-action",
+    "x-tc-language",
+    "x-tc-region",
+    "x-tc-timestamp",
+    "x-tc-token",
+    "x-tc-version",
+];
 
-        if let Some(err) = response.response.error {
-            return Err(Error::Api {
-                err,
-                request_id: response.response.request_id,
-            });
+/// the options of a single api call, they override the [`Client`] settings
+#[derive(Debug, Clone, Default)]
+pub struct RequestOptions {
+    region: Option<String>,
+    language: Option<Language>,
+    deadline: Option<Instant>,
+    response_size_limit: Option<usize>,
+    headers: HeaderMap,
+}
+
+impl RequestOptions {
+    /// create the options which override nothing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// set the region, the empty region means the api is not regional
+    pub fn with_region(mut self, region: impl Into<String>) -> Self {
+        self.region = Some(region.into());
+
+        self
+    }
+
+    /// set the `X-TC-Language` header
+    pub fn with_language(mut self, language: Language) -> Self {
+        self.language = Some(language);
+
+        self
+    }
+
+    /// set the deadline of the call, includes all retries, the earlier one of the deadline and
+    /// the client total timeout is used
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+
+        self
+    }
+
+    /// limit the http response body size
+    pub fn with_response_size_limit(mut self, limit: usize) -> Self {
+        self.response_size_limit = Some(limit);
+
+        self
+    }
+
+    /// add the `X-TC-*` header, the headers set by the client such as the `X-TC-Action` and
+    /// `X-TC-Region` can't be added, the call fails with the other headers
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.append(name, value);
+
+        self
+    }
+}
+
+/// the metadata of the api response
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ResponseMeta {
+    /// the request id
+    pub request_id: String,
+
+    /// the http status
+    pub status: StatusCode,
+
+    /// the response headers
+    pub headers: HeaderMap,
+
+    /// the negotiated http version
+    pub version: Version,
+
+    /// the number of the http requests sent, including the retries
+    pub attempts: u32,
+
+    /// the wall-clock latency of the whole call, including the retries and backoff
+    pub latency: Duration,
+}
+
+/// [`Client`] builder
+///
+/// the credential must be set by [`ClientBuilder::auth`] or
+/// [`ClientBuilder::credential_provider`], other options are optional
+#[derive(Debug, Default)]
+pub struct ClientBuilder {
+    region: String,
+    credential_provider: Option<Arc<dyn CredentialProvider>>,
+    profile: Option<ProfileProvider>,
+    endpoint_resolver: Option<Arc<dyn EndpointResolver>>,
+    transport: Option<Arc<dyn Transport>>,
+    connect_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    total_timeout: Option<Duration>,
+    response_size_limit: Option<usize>,
+    user_agent: Option<String>,
+    language: Option<Language>,
+    http_version: HttpVersion,
+    proxy: Option<Proxy>,
+    disable_proxy: bool,
+    tls_config: TlsConfig,
+    #[cfg(feature = "tower")]
+    pub(crate) transport_layers: Vec<crate::tower::TransportLayer>,
+    retry_policy: Option<RetryPolicy>,
+    rate_limit: Option<RateLimitLayer>,
+    disable_clock_skew_correction: bool,
+}
+
+impl ClientBuilder {
+    /// set the region, for example: `ap-guangzhou`
+    ///
+    /// some apis don't need the region, the `X-TC-Region` header won't be sent if the region is
+    /// empty
+    pub fn region(mut self, region: impl Into<String>) -> Self {
+        self.region = region.into();
+
+        self
+    }
+
+    /// set the static [`Auth`]
+    pub fn auth(mut self, auth: Auth) -> Self {
+        self.credential_provider = Some(Arc::new(auth));
+
+        self
+    }
+
+    /// set the [`CredentialProvider`], the client asks it for the credential before signing every
+    /// request
+    pub fn credential_provider(
+        mut self,
+        credential_provider: impl CredentialProvider + 'static,
+    ) -> Self {
+        self.credential_provider = Some(Arc::new(credential_provider));
+
+        self
+    }
+
+    /// use the tccli [`ProfileProvider`] as the [`CredentialProvider`]
+    ///
+    /// if the region is not set, the default region of the profile is used
+    pub fn profile(mut self, profile: ProfileProvider) -> Self {
+        self.credential_provider = Some(Arc::new(profile.clone()));
+        self.profile = Some(profile);
+
+        self
+    }
+
+    /// set the [`EndpointResolver`], the default is [`DefaultEndpointResolver`] which uses the
+    /// [`Api::HOST`]
+    pub fn endpoint_resolver(mut self, endpoint_resolver: impl EndpointResolver + 'static) -> Self {
+        self.endpoint_resolver = Some(Arc::new(endpoint_resolver));
+
+        self
+    }
+
+    /// set the [`Transport`] which sends the http request, the default is selected by the tls
+    /// features, see the [`transport`](crate::transport) module; it must be set if no tls
+    /// feature is enabled
+    ///
+    /// the [`ClientBuilder::connect_timeout`], [`ClientBuilder::http_version`],
+    /// [`ClientBuilder::proxy`] and [`ClientBuilder::tls_config`] only apply to the default
+    /// transport
+    pub fn transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = Some(Arc::new(transport));
+
+        self
+    }
+
+    /// set the [`Proxy`], the default is read by [`Proxy::from_env`]
+    ///
+    /// it only applies to the default transport
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy = Some(proxy);
+
+        self
+    }
+
+    /// connect the api server directly, the proxy environment variables are ignored
+    pub fn no_proxy(mut self) -> Self {
+        self.disable_proxy = true;
+
+        self
+    }
+
+    /// set the [`TlsConfig`], the default trusts the native root certificates
+    ///
+    /// it only applies to the default transport
+    pub fn tls_config(mut self, tls_config: TlsConfig) -> Self {
+        self.tls_config = tls_config;
+
+        self
+    }
+
+    /// set the connect timeout, it covers the tcp connect and the tls handshake
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+
+        self
+    }
+
+    /// set the timeout of a single http request, includes reading the response body
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+
+        self
+    }
+
+    /// set the total timeout of an api call, includes all retries
+    pub fn total_timeout(mut self, timeout: Duration) -> Self {
+        self.total_timeout = Some(timeout);
+
+        self
+    }
+
+    /// limit the http response body size, if the response body size is bigger than `limit`, will
+    /// return error
+    pub fn response_size_limit(mut self, limit: usize) -> Self {
+        self.response_size_limit = Some(limit);
+
+        self
+    }
+
+    /// set the `User-Agent` header
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+
+        self
+    }
+
+    /// set the `X-TC-Language` header, the error message language is decided by it
+    pub fn language(mut self, language: Language) -> Self {
+        self.language = Some(language);
+
+        self
+    }
+
+    /// set the http version preference, the default is [`HttpVersion::Auto`]
+    pub fn http_version(mut self, http_version: HttpVersion) -> Self {
+        self.http_version = http_version;
+
+        self
+    }
+
+    /// set the [`RetryPolicy`], the default is [`RetryPolicy::default`]
+    ///
+    /// use [`RetryPolicy::disabled`] to disable the retry
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+
+        self
+    }
+
+    /// limit the http requests sent by the client to `num` requests per `per` duration, the
+    /// retries are also limited, the request exceeding the limit waits until the next period
+    ///
+    /// the limit is shared by the clones of the client
+    pub fn rate_limit(mut self, num: u64, per: Duration) -> Self {
+        self.rate_limit = Some(RateLimitLayer::new(num, per));
+
+        self
+    }
+
+    /// enable or disable the clock skew correction, it is enabled by default
+    ///
+    /// when enabled, the offset between the server clock and the local clock is tracked from the
+    /// `Date` response header and applied to the `X-TC-Timestamp`, the request failed with
+    /// `AuthFailure.SignatureExpire` is re-sent once after the offset is corrected
+    pub fn clock_skew_correction(mut self, enable: bool) -> Self {
+        self.disable_clock_skew_correction = !enable;
+
+        self
+    }
+
+    /// build the [`Client`]
+    ///
+    /// it fails if the credential provider is not set, or the default transport can't be
+    /// created, for example the tls roots are invalid or no tls feature is enabled
+    pub fn build(mut self) -> Result<Client, Error> {
+        let credential_provider = self
+            .credential_provider
+            .take()
+            .ok_or_else(|| Error::Other("miss credential provider".into()))?;
+
+        if self.region.is_empty() {
+            if let Some(region) = self
+                .profile
+                .as_ref()
+                .map(|profile| profile.region())
+                .transpose()?
+            {
+                self.region = region.unwrap_or_default();
+            }
         }
 
-        match response.response.response {
-            None => Err(Error::Other("miss response".into())),
-            Some(resp) => Ok((resp, response.response.request_id)),
+        self.finish(credential_provider)
+    }
+
+    /// create the default transport with the options of the builder
+    fn default_transport(&self) -> Result<Arc<dyn Transport>, Error> {
+        let proxy = match (&self.proxy, self.disable_proxy) {
+            (_, true) => None,
+            (Some(proxy), false) => Some(proxy.clone()),
+            (None, false) => Proxy::from_env(),
+        };
+
+        new_default_transport(&TransportConfig {
+            connect_timeout: self.connect_timeout,
+            http_version: self.http_version,
+            proxy,
+            tls: self.tls_config.clone(),
+        })
+    }
+
+    fn finish(self, credential_provider: Arc<dyn CredentialProvider>) -> Result<Client, Error> {
+        let transport = match &self.transport {
+            Some(transport) => transport.clone(),
+            None => self.default_transport()?,
+        };
+        #[cfg(feature = "tower")]
+        let transport = self
+            .transport_layers
+            .into_iter()
+            .fold(transport, |transport, layer| layer.apply(transport));
+
+        let mut signing = SigningLayer::with_provider(credential_provider)
+            .with_clock_skew_correction(!self.disable_clock_skew_correction);
+        if let Some(user_agent) = self.user_agent {
+            signing = signing.with_user_agent(user_agent);
         }
+
+        let transport = TransportService::with_transport(transport);
+        let rate_limit = self.rate_limit.unwrap_or_else(RateLimitLayer::unlimited);
+        let service = TraceLayer.layer(
+            RetryLayer::new(self.retry_policy.unwrap_or_default())
+                .layer(rate_limit.layer(
+                    TimeoutLayer::new(self.timeout).layer(signing.layer(transport.clone())),
+                )),
+        );
+
+        Ok(Client {
+            region: self.region,
+            response_size_limit: self.response_size_limit,
+            endpoint_resolver: self
+                .endpoint_resolver
+                .unwrap_or_else(|| Arc::new(DefaultEndpointResolver::default())),
+            total_timeout: self.total_timeout,
+            language: self.language,
+            signing,
+            transport,
+            service,
+        })
     }
+}
 
-    #[instrument(level = "trace", err)]
-    fn create_request<A: Api>(&self, payload: Vec<u8>) -> Result<Request<Body>, Error> {
-        let now = OffsetDateTime::now_utc();
-        let authorization = tc3_hmac::calculate_authorization(
-            &self.auth.secret_id,
-            &self.auth.secret_key,
-            A::SERVICE,
-            A::HOST,
-            &payload,
-            &now,
-        )
-        .map_err(Error::Other)?;
+/// the http version preference
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum HttpVersion {
+    /// negotiate the http version by tls alpn
+    #[default]
+    Auto,
+
+    /// only use http1
+    Http1Only,
+
+    /// only use http2
+    Http2Only,
+}
 
-        let request = Request::builder()
-            .uri("https://tmt.tencentcloudapi.com")
-            .method(Method::POST)
-            .header("Authorization", authorization)
-            .header("Content-Type", "application/json; charset=utf-8")
-            .header("X-TC-Action", A::ACTION)
-            .header("X-TC-Timestamp", now.unix_timestamp())
-            .header("X-TC-Version", A::VERSION)
-            .header("X-TC-Region", &self.region)
-            .body(Body::from(payload))
-            .map_err(|err| Error::Other(err.into()))?;
+/// the `X-TC-Language` value
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Language {
+    /// simplified chinese, `zh-CN`
+    ZhCn,
 
-        Ok(request)
+    /// english, `en-US`
+    EnUs,
+}
+
+impl Language {
+    /// the `X-TC-Language` header value
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Language::ZhCn => "zh-CN",
+            Language::EnUs => "en-US",
+        }
     }
 }
 
 /// tencentcloud api auth
 ///
-/// currently only support `secret_key` and `secret_id`
+/// support the permanent `secret_key` and `secret_id`, and the temporary credential which also
+/// carries a session `token` and an expiration
 #[derive(Clone)]
 pub struct Auth {
     secret_key: String,
     secret_id: String,
+    token: Option<String>,
+    expiration: Option<OffsetDateTime>,
 }
 
 impl Auth {
@@ -135,32 +782,51 @@ impl Auth {
         Self {
             secret_key,
             secret_id,
+            token: None,
+            expiration: None,
         }
     }
+
+    /// set the session token of the temporary credential, it will be sent as the `X-TC-Token`
+    /// header
+    pub fn with_token(mut self, token: String) -> Self {
+        self.token = Some(token);
+
+        self
+    }
+
+    /// set the expiration of the temporary credential
+    pub fn with_expiration(mut self, expiration: OffsetDateTime) -> Self {
+        self.expiration = Some(expiration);
+
+        self
+    }
+
+    /// the secret id
+    pub fn secret_id(&self) -> &str {
+        &self.secret_id
+    }
+
+    pub(crate) fn secret_key(&self) -> &str {
+        &self.secret_key
+    }
+
+    /// the session token, only the temporary credential has it
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    /// the expiration, `None` means the credential never expires
+    pub fn expiration(&self) -> Option<OffsetDateTime> {
+        self.expiration
+    }
 }
 
 impl Debug for Auth {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Auth")
             .field("secret_id", &self.secret_id)
+            .field("expiration", &self.expiration)
             .finish_non_exhaustive()
     }
 }
-
-#[derive(Debug, Deserialize)]
-struct Response<T> {
-    #[serde(rename = "Response")]
-    response: ResponseDetail<T>,
-}
-
-#[derive(Debug, Deserialize)]
-struct ResponseDetail<T> {
-    #[serde(rename = "RequestId")]
-    request_id: String,
-
-    #[serde(flatten)]
-    response: Option<T>,
-
-    #[serde(rename = "Error")]
-    error: Option<ApiError>,
-}
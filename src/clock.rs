@@ -0,0 +1,95 @@
+//! clock skew correction
+//!
+//! the tencentcloud api rejects the request whose `X-TC-Timestamp` differs from the server time
+//! by more than 5 minutes, the [`ClockSkew`] tracks the offset from the `Date` header of the
+//! responses and corrects the signing time
+
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use hyper::header::DATE;
+use hyper::HeaderMap;
+use time::{Duration, OffsetDateTime};
+use tracing::debug;
+
+/// the offset less than it is treated as no skew, the `Date` header is only accurate to seconds
+const SKEW_THRESHOLD_SECS: i64 = 30;
+
+/// the offset between the server clock and the local clock
+#[derive(Debug, Clone, Default)]
+pub struct ClockSkew {
+    offset_secs: Arc<AtomicI64>,
+}
+
+impl ClockSkew {
+    /// the corrected current time
+    pub fn now(&self) -> OffsetDateTime {
+        OffsetDateTime::now_utc() + Duration::seconds(self.offset_secs())
+    }
+
+    /// the offset in seconds, the server time minus the local time
+    pub fn offset_secs(&self) -> i64 {
+        self.offset_secs.load(Ordering::Acquire)
+    }
+
+    /// update the offset by the `Date` header of the response
+    pub fn observe(&self, headers: &HeaderMap) {
+        let server_time = match headers
+            .get(DATE)
+            .and_then(|date| date.to_str().ok())
+            .and_then(|date| httpdate::parse_http_date(date).ok())
+        {
+            None => return,
+            Some(server_time) => server_time,
+        };
+
+        let offset_secs = match server_time.duration_since(SystemTime::now()) {
+            Ok(ahead) => ahead.as_secs() as i64,
+            Err(err) => -(err.duration().as_secs() as i64),
+        };
+        let offset_secs = if offset_secs.abs() < SKEW_THRESHOLD_SECS {
+            0
+        } else {
+            offset_secs
+        };
+
+        let old_offset_secs = self.offset_secs.swap(offset_secs, Ordering::AcqRel);
+        if old_offset_secs != offset_secs {
+            debug!(old_offset_secs, offset_secs, "clock skew is changed");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(offset_secs: i64) -> HeaderMap {
+        let now = SystemTime::now();
+        let time = match offset_secs {
+            0.. => now + std::time::Duration::from_secs(offset_secs as u64),
+            _ => now - std::time::Duration::from_secs(offset_secs.unsigned_abs()),
+        };
+
+        HeaderMap::from_iter([(DATE, httpdate::fmt_http_date(time).parse().unwrap())])
+    }
+
+    #[test]
+    fn observe_date() {
+        let clock_skew = ClockSkew::default();
+
+        clock_skew.observe(&date(-600));
+        assert!((-601..=-599).contains(&clock_skew.offset_secs()));
+
+        // the clones share the offset
+        clock_skew.clone().observe(&date(600));
+        assert!((599..=601).contains(&clock_skew.offset_secs()));
+
+        // the small offset is treated as no skew, the invalid date is ignored
+        clock_skew.observe(&date(10));
+        assert_eq!(clock_skew.offset_secs(), 0);
+        clock_skew.observe(&HeaderMap::from_iter([(DATE, "invalid".parse().unwrap())]));
+        assert_eq!(clock_skew.offset_secs(), 0);
+    }
+}
@@ -0,0 +1,429 @@
+//! credential providers
+//!
+//! the [`Client`](crate::Client) asks the [`CredentialProvider`] for the [`Auth`] before signing
+//! every request, so the credential can be rotated without rebuilding the client
+//!
+//! the built-in providers:
+//!
+//! - [`Auth`]: the static credential
+//! - [`EnvProvider`]: read the credential from the environment variables
+//! - [`ProfileProvider`]: read the credential from the files written by tccli
+//! - [`CvmRoleProvider`]: get the role credential from the cvm metadata service
+//! - [`AssumeRoleProvider`]: assume the role by the sts `AssumeRole` api
+//! - [`WebIdentityProvider`]: assume the role by the sts `AssumeRoleWithWebIdentity` api, for
+//!   the tke oidc
+//! - [`CachingProvider`]: cache the credential of another provider and refresh it before expiry
+
+use std::env;
+use std::env::VarError;
+use std::fmt::Debug;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::future::{ready, BoxFuture};
+use futures_util::lock::Mutex;
+use futures_util::FutureExt;
+use time::OffsetDateTime;
+use tracing::{instrument, warn};
+
+pub use self::cvm::{CvmRoleProvider, DEFAULT_METADATA_URL};
+pub use self::profile::{ProfileProvider, ENV_PROFILE};
+pub use self::sts::{
+    AssumeRoleProvider, WebIdentityProvider, ENV_TKE_PROVIDER_ID, ENV_TKE_REGION, ENV_TKE_ROLE_ARN,
+    ENV_TKE_WEB_IDENTITY_TOKEN_FILE,
+};
+use crate::client::Auth;
+use crate::error::Error;
+
+mod cvm;
+mod profile;
+mod sts;
+
+/// the environment variable of the secret id
+pub const ENV_SECRET_ID: &str = "TENCENTCLOUD_SECRET_ID";
+
+/// the environment variable of the secret key
+pub const ENV_SECRET_KEY: &str = "TENCENTCLOUD_SECRET_KEY";
+
+/// the environment variable of the session token
+pub const ENV_SESSION_TOKEN: &str = "TENCENTCLOUD_SESSION_TOKEN";
+
+/// the default duration to refresh the credential before it expires
+const DEFAULT_REFRESH_AHEAD: Duration = Duration::from_secs(5 * 60);
+
+/// provide the [`Auth`] to sign the request
+pub trait CredentialProvider: Debug + Send + Sync {
+    /// get the credential
+    fn credential(&self) -> BoxFuture<'_, Result<Auth, Error>>;
+}
+
+impl CredentialProvider for Auth {
+    fn credential(&self) -> BoxFuture<'_, Result<Auth, Error>> {
+        ready(Ok(self.clone())).boxed()
+    }
+}
+
+impl<P: CredentialProvider + ?Sized> CredentialProvider for Arc<P> {
+    fn credential(&self) -> BoxFuture<'_, Result<Auth, Error>> {
+        (**self).credential()
+    }
+}
+
+/// read the credential from the environment variables
+///
+/// the `TENCENTCLOUD_SECRET_ID` and `TENCENTCLOUD_SECRET_KEY` are required, the
+/// `TENCENTCLOUD_SESSION_TOKEN` is optional
+#[derive(Debug, Copy, Clone, Default)]
+pub struct EnvProvider {
+    _priv: (),
+}
+
+impl EnvProvider {
+    /// create the environment variables provider
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn load(&self) -> Result<Auth, Error> {
+        Self::load_from(read_env)
+    }
+
+    /// read the credential from the variables got by the `lookup`, the empty value is treated
+    /// as unset
+    fn load_from(lookup: impl Fn(&str) -> Result<Option<String>, Error>) -> Result<Auth, Error> {
+        let read = |key| lookup(key).map(|value| value.filter(|value| !value.is_empty()));
+
+        let secret_id = read(ENV_SECRET_ID)?
+            .ok_or_else(|| Error::Credential(format!("miss env {ENV_SECRET_ID}").into()))?;
+        let secret_key = read(ENV_SECRET_KEY)?
+            .ok_or_else(|| Error::Credential(format!("miss env {ENV_SECRET_KEY}").into()))?;
+
+        let auth = Auth::new(secret_key, secret_id);
+
+        Ok(match read(ENV_SESSION_TOKEN)? {
+            None => auth,
+            Some(token) => auth.with_token(token),
+        })
+    }
+}
+
+impl CredentialProvider for EnvProvider {
+    fn credential(&self) -> BoxFuture<'_, Result<Auth, Error>> {
+        ready(self.load()).boxed()
+    }
+}
+
+/// read the env `key`
+fn read_env(key: &str) -> Result<Option<String>, Error> {
+    match env::var(key) {
+        Ok(value) => Ok(Some(value)),
+        Err(VarError::NotPresent) => Ok(None),
+        Err(err) => Err(Error::Credential(
+            format!("read env {key} failed: {err}").into(),
+        )),
+    }
+}
+
+/// cache the credential of the `P` provider
+///
+/// the cached credential is refreshed when it will expire in the `refresh_ahead` duration, the
+/// concurrent callers share one refresh; the credential without expiration is cached forever
+#[derive(Debug)]
+pub struct CachingProvider<P> {
+    inner: P,
+    cache: CredentialCache,
+}
+
+impl<P: CredentialProvider> CachingProvider<P> {
+    /// cache the credential of the `inner` provider, refresh it 5 minutes before expiry
+    pub fn new(inner: P) -> Self {
+        Self {
+            inner,
+            cache: CredentialCache::new(DEFAULT_REFRESH_AHEAD),
+        }
+    }
+
+    /// set the duration to refresh the credential before it expires
+    pub fn with_refresh_ahead(mut self, refresh_ahead: Duration) -> Self {
+        self.cache = CredentialCache::new(refresh_ahead);
+
+        self
+    }
+}
+
+impl<P: CredentialProvider> CredentialProvider for CachingProvider<P> {
+    fn credential(&self) -> BoxFuture<'_, Result<Auth, Error>> {
+        self.cache
+            .get_or_refresh(|| self.inner.credential())
+            .boxed()
+    }
+}
+
+/// the credential cache used by the refreshable providers
+#[derive(Debug)]
+pub(crate) struct CredentialCache {
+    refresh_ahead: Duration,
+    auth: Mutex<Option<Auth>>,
+}
+
+impl CredentialCache {
+    pub(crate) fn new(refresh_ahead: Duration) -> Self {
+        Self {
+            refresh_ahead,
+            auth: Mutex::new(None),
+        }
+    }
+
+    /// get the cached credential, or refresh it by the future created by the `refresh`, which is
+    /// only called when the cached credential needs refreshing
+    ///
+    /// the lock is held during refreshing, so only one refresh runs at a time
+    #[instrument(level = "trace", skip(self, refresh), err)]
+    pub(crate) async fn get_or_refresh<F, Fut>(&self, refresh: F) -> Result<Auth, Error>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Auth, Error>>,
+    {
+        let mut cached = self.auth.lock().await;
+        let now = OffsetDateTime::now_utc();
+
+        if let Some(auth) = cached.as_ref() {
+            match auth.expiration() {
+                None => return Ok(auth.clone()),
+                // the huge refresh ahead overflows, always refresh
+                Some(expiration)
+                    if expiration
+                        .checked_sub(self.refresh_ahead.try_into().unwrap_or(time::Duration::MAX))
+                        .is_some_and(|refresh_at| refresh_at > now) =>
+                {
+                    return Ok(auth.clone())
+                }
+                _ => {}
+            }
+        }
+
+        match refresh().await {
+            Ok(auth) => {
+                *cached = Some(auth.clone());
+
+                Ok(auth)
+            }
+
+            Err(err) => match cached.as_ref() {
+                // the cached credential is not expired yet, use it and refresh next time
+                Some(auth) if auth.expiration().is_some_and(|expiration| expiration > now) => {
+                    warn!(%err, "refresh credential failed, use the cached credential");
+
+                    Ok(auth.clone())
+                }
+
+                _ => Err(err),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, VecDeque};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    /// the provider which returns the queued credentials in order, and counts the calls
+    #[derive(Debug, Default)]
+    struct QueuedProvider {
+        calls: AtomicUsize,
+        credentials: std::sync::Mutex<VecDeque<Result<Auth, Error>>>,
+    }
+
+    impl QueuedProvider {
+        fn new(credentials: impl IntoIterator<Item = Result<Auth, Error>>) -> Arc<Self> {
+            Arc::new(Self {
+                calls: AtomicUsize::new(0),
+                credentials: std::sync::Mutex::new(credentials.into_iter().collect()),
+            })
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    impl CredentialProvider for QueuedProvider {
+        fn credential(&self) -> BoxFuture<'_, Result<Auth, Error>> {
+            // count the calls rather than the polls, the cached credential must not call it
+            self.calls.fetch_add(1, Ordering::SeqCst);
+
+            async move {
+                // let the concurrent callers run during refreshing
+                tokio::task::yield_now().await;
+
+                self.credentials
+                    .lock()
+                    .unwrap()
+                    .pop_front()
+                    .expect("no queued credential")
+            }
+            .boxed()
+        }
+    }
+
+    /// the credential `id` which expires in the `expires_in`
+    fn auth(id: &str, expires_in: Duration) -> Auth {
+        Auth::new("key".to_string(), id.to_string())
+            .with_expiration(OffsetDateTime::now_utc() + expires_in)
+    }
+
+    fn refresh_failed() -> Result<Auth, Error> {
+        Err(Error::Credential("refresh failed".into()))
+    }
+
+    #[test]
+    fn env_provider() {
+        let load = |vars: &[(&str, &str)]| {
+            let vars = vars
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect::<HashMap<_, _>>();
+
+            EnvProvider::load_from(|name| Ok(vars.get(name).cloned()))
+        };
+
+        let err = load(&[(ENV_SECRET_KEY, "key")]).unwrap_err();
+        assert!(err.to_string().contains(ENV_SECRET_ID), "{err}");
+
+        // the empty value is treated as unset
+        let err = load(&[(ENV_SECRET_ID, "id"), (ENV_SECRET_KEY, "")]).unwrap_err();
+        assert!(err.to_string().contains(ENV_SECRET_KEY), "{err}");
+
+        let auth = load(&[(ENV_SECRET_ID, "id"), (ENV_SECRET_KEY, "key")]).unwrap();
+        assert_eq!((auth.secret_id(), auth.secret_key()), ("id", "key"));
+        assert_eq!(auth.token(), None);
+
+        let auth = load(&[
+            (ENV_SECRET_ID, "id"),
+            (ENV_SECRET_KEY, "key"),
+            (ENV_SESSION_TOKEN, ""),
+        ])
+        .unwrap();
+        assert_eq!(auth.token(), None);
+
+        let auth = load(&[
+            (ENV_SECRET_ID, "id"),
+            (ENV_SECRET_KEY, "key"),
+            (ENV_SESSION_TOKEN, "token"),
+        ])
+        .unwrap();
+        assert_eq!(auth.token(), Some("token"));
+
+        let err =
+            EnvProvider::load_from(|_| Err(Error::Credential("not unicode".into()))).unwrap_err();
+        assert!(err.to_string().contains("not unicode"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn refresh_ahead_of_expiry() {
+        let inner = QueuedProvider::new([
+            Ok(auth("first", Duration::from_secs(10 * 60))),
+            Ok(auth("second", Duration::from_secs(60 * 60))),
+        ]);
+
+        // the credential is fresh enough to be cached
+        let provider = CachingProvider::new(inner.clone());
+        assert_eq!(provider.credential().await.unwrap().secret_id(), "first");
+        assert_eq!(provider.credential().await.unwrap().secret_id(), "first");
+        assert_eq!(inner.calls(), 1);
+
+        // the credential will expire in the refresh ahead duration
+        let inner = QueuedProvider::new([
+            Ok(auth("first", Duration::from_secs(10 * 60))),
+            Ok(auth("second", Duration::from_secs(60 * 60))),
+        ]);
+        let provider =
+            CachingProvider::new(inner.clone()).with_refresh_ahead(Duration::from_secs(15 * 60));
+        assert_eq!(provider.credential().await.unwrap().secret_id(), "first");
+        assert_eq!(provider.credential().await.unwrap().secret_id(), "second");
+        assert_eq!(inner.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn huge_refresh_ahead_always_refreshes() {
+        let inner = QueuedProvider::new([
+            Ok(auth("first", Duration::from_secs(60 * 60))),
+            Ok(auth("second", Duration::from_secs(60 * 60))),
+        ]);
+        let provider = CachingProvider::new(inner.clone()).with_refresh_ahead(Duration::MAX);
+
+        assert_eq!(provider.credential().await.unwrap().secret_id(), "first");
+        assert_eq!(provider.credential().await.unwrap().secret_id(), "second");
+        assert_eq!(inner.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn credential_without_expiration_is_cached() {
+        let inner = QueuedProvider::new([Ok(Auth::new("key".to_string(), "id".to_string()))]);
+        let provider = CachingProvider::new(inner.clone());
+
+        provider.credential().await.unwrap();
+        provider.credential().await.unwrap();
+
+        assert_eq!(inner.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn fall_back_to_valid_credential() {
+        let inner = QueuedProvider::new([
+            Ok(auth("cached", Duration::from_secs(2 * 60))),
+            refresh_failed(),
+            Ok(auth("refreshed", Duration::from_secs(60 * 60))),
+        ]);
+        let provider = CachingProvider::new(inner.clone());
+
+        assert_eq!(provider.credential().await.unwrap().secret_id(), "cached");
+
+        // the refresh fails, the cached credential is not expired yet
+        assert_eq!(provider.credential().await.unwrap().secret_id(), "cached");
+
+        // the refresh is tried again next time
+        assert_eq!(
+            provider.credential().await.unwrap().secret_id(),
+            "refreshed"
+        );
+        assert_eq!(inner.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn expired_credential_is_not_used() {
+        let cache = CredentialCache::new(DEFAULT_REFRESH_AHEAD);
+        *cache.auth.lock().await = Some(
+            Auth::new("key".to_string(), "expired".to_string())
+                .with_expiration(OffsetDateTime::now_utc() - Duration::from_secs(1)),
+        );
+
+        let err = cache
+            .get_or_refresh(|| async { refresh_failed() })
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("refresh failed"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn concurrent_callers_share_one_refresh() {
+        let inner = QueuedProvider::new([Ok(auth("first", Duration::from_secs(60 * 60)))]);
+        let provider = CachingProvider::new(inner.clone());
+
+        let (first, second, third) = tokio::join!(
+            provider.credential(),
+            provider.credential(),
+            provider.credential()
+        );
+
+        for auth in [first, second, third] {
+            assert_eq!(auth.unwrap().secret_id(), "first");
+        }
+        assert_eq!(inner.calls(), 1);
+    }
+}
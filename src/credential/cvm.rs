@@ -0,0 +1,328 @@
+//! the cvm instance role provider
+
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use futures_util::FutureExt;
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::{Request, StatusCode};
+use serde::Deserialize;
+use time::OffsetDateTime;
+use tracing::{instrument, trace};
+
+use crate::client::Auth;
+use crate::credential::{CredentialCache, CredentialProvider, DEFAULT_REFRESH_AHEAD};
+use crate::error::Error;
+use crate::http_client::new_default_transport;
+use crate::timer;
+use crate::tls::TlsConfig;
+use crate::transport::{Transport, TransportConfig};
+
+/// the default cvm metadata service base url
+pub const DEFAULT_METADATA_URL: &str = "http://metadata.tencentyun.com/latest";
+
+/// the security credentials path of the metadata service
+const SECURITY_CREDENTIALS_PATH: &str = "meta-data/cam/security-credentials";
+
+/// the metadata response size limit
+const RESPONSE_SIZE_LIMIT: usize = 64 * 1024;
+
+/// the metadata request timeout
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// get the role credential from the cvm metadata service
+///
+/// the credential is cached and refreshed before it expires; if the role is not set, the role
+/// attached to the cvm instance is discovered from the metadata service
+#[derive(Debug)]
+pub struct CvmRoleProvider {
+    base_url: String,
+    role: Option<String>,
+    timeout: Duration,
+    transport: OnceLock<Arc<dyn Transport>>,
+    cache: CredentialCache,
+}
+
+impl Default for CvmRoleProvider {
+    fn default() -> Self {
+        Self {
+            base_url: DEFAULT_METADATA_URL.to_string(),
+            role: None,
+            timeout: DEFAULT_TIMEOUT,
+            transport: OnceLock::new(),
+            cache: CredentialCache::new(DEFAULT_REFRESH_AHEAD),
+        }
+    }
+}
+
+impl CvmRoleProvider {
+    /// create the provider
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// use the `role` instead of discovering it
+    pub fn with_role(mut self, role: impl Into<String>) -> Self {
+        self.role = Some(role.into());
+
+        self
+    }
+
+    /// set the metadata service base url, the default is [`DEFAULT_METADATA_URL`]
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+
+        self
+    }
+
+    /// set the timeout of a metadata request, the default is 5s
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+
+        self
+    }
+
+    /// set the [`Transport`] which sends the metadata request
+    pub fn with_transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = OnceLock::from(Arc::new(transport) as Arc<dyn Transport>);
+
+        self
+    }
+
+    /// set the duration to refresh the credential before it expires, the default is 5 minutes
+    pub fn with_refresh_ahead(mut self, refresh_ahead: Duration) -> Self {
+        self.cache = CredentialCache::new(refresh_ahead);
+
+        self
+    }
+
+    /// get the transport, the default one is created on first use
+    fn transport(&self) -> Result<&Arc<dyn Transport>, Error> {
+        if let Some(transport) = self.transport.get() {
+            return Ok(transport);
+        }
+
+        // the metadata service is only reachable directly by http, never use the proxy and
+        // don't need the root certificates
+        let transport = new_default_transport(
+            &TransportConfig::new().with_tls_config(TlsConfig::new().without_native_roots()),
+        )?;
+
+        Ok(self.transport.get_or_init(|| transport))
+    }
+
+    #[instrument(level = "trace", skip(self), err)]
+    async fn fetch(&self) -> Result<Auth, Error> {
+        let role = match &self.role {
+            Some(role) => role.clone(),
+            None => {
+                let roles = self.get(SECURITY_CREDENTIALS_PATH).await?;
+
+                roles
+                    .lines()
+                    .map(str::trim)
+                    .find(|role| !role.is_empty())
+                    .ok_or_else(|| Error::Credential("the cvm instance has no role".into()))?
+                    .to_string()
+            }
+        };
+
+        trace!(%role, "get cvm role done");
+
+        let body = self
+            .get(&format!("{SECURITY_CREDENTIALS_PATH}/{role}"))
+            .await?;
+        let credential = serde_json::from_str::<RoleCredential>(&body).map_err(|err| {
+            Error::Credential(format!("parse role credential failed: {err}").into())
+        })?;
+
+        if credential.code != "Success" {
+            return Err(Error::Credential(
+                format!("get role {role} credential failed: {}", credential.code).into(),
+            ));
+        }
+
+        let expiration = OffsetDateTime::from_unix_timestamp(credential.expired_time)
+            .map_err(|err| Error::Credential(err.into()))?;
+
+        Ok(
+            Auth::new(credential.tmp_secret_key, credential.tmp_secret_id)
+                .with_token(credential.token)
+                .with_expiration(expiration),
+        )
+    }
+
+    async fn get(&self, path: &str) -> Result<String, Error> {
+        let uri = format!("{}/{path}", self.base_url);
+        let request = Request::get(&uri)
+            .body(Full::default())
+            .map_err(|err| Error::Credential(err.into()))?;
+
+        let body = timer::timeout(self.timeout, async {
+            let response = self.transport()?.send(request).await?;
+            if response.status() != StatusCode::OK {
+                return Err(Error::Credential(
+                    format!("get {uri} failed, status code: {}", response.status()).into(),
+                ));
+            }
+
+            Limited::new(response.into_body(), RESPONSE_SIZE_LIMIT)
+                .boxed()
+                .collect()
+                .await
+                .map(|body| body.to_bytes())
+                .map_err(Error::Credential)
+        })
+        .await
+        .map_err(|_| Error::Timeout)??;
+
+        String::from_utf8(body.to_vec()).map_err(|err| Error::Credential(err.into()))
+    }
+}
+
+impl CredentialProvider for CvmRoleProvider {
+    fn credential(&self) -> BoxFuture<'_, Result<Auth, Error>> {
+        self.cache.get_or_refresh(|| self.fetch()).boxed()
+    }
+}
+
+/// the role credential returned by the metadata service
+#[derive(Debug, Deserialize)]
+struct RoleCredential {
+    #[serde(rename = "TmpSecretId")]
+    tmp_secret_id: String,
+
+    #[serde(rename = "TmpSecretKey")]
+    tmp_secret_key: String,
+
+    #[serde(rename = "Token")]
+    token: String,
+
+    #[serde(rename = "ExpiredTime")]
+    expired_time: i64,
+
+    #[serde(rename = "Code")]
+    code: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::pending;
+
+    use hyper::HeaderMap;
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_util::MockTransport;
+    use crate::transport::{RequestBody, ResponseBody};
+
+    const BASE_URL: &str = "http://metadata.test/latest";
+
+    fn role_credential(code: &str, expired_time: i64) -> String {
+        json!({
+            "TmpSecretId": "tmp-id",
+            "TmpSecretKey": "tmp-key",
+            "Token": "tmp-token",
+            "ExpiredTime": expired_time,
+            "Expiration": "2026-10-17T10:00:00Z",
+            "Code": code,
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn discover_role_and_cache_credential() {
+        let expired_time = OffsetDateTime::now_utc().unix_timestamp() + 3600;
+        let transport = MockTransport::new();
+        transport
+            .push_text(StatusCode::OK, HeaderMap::new(), "\nrole-name\n")
+            .push_text(
+                StatusCode::OK,
+                HeaderMap::new(),
+                role_credential("Success", expired_time),
+            );
+        let provider = CvmRoleProvider::new()
+            .with_base_url(format!("{BASE_URL}/"))
+            .with_transport(transport.clone());
+
+        let auth = provider.credential().await.unwrap();
+        assert_eq!(auth.secret_id(), "tmp-id");
+        assert_eq!(auth.secret_key(), "tmp-key");
+        assert_eq!(auth.token(), Some("tmp-token"));
+        assert_eq!(auth.expiration().unwrap().unix_timestamp(), expired_time);
+
+        // the credential is cached until it will expire
+        provider.credential().await.unwrap();
+
+        let uris = transport
+            .requests()
+            .into_iter()
+            .map(|request| request.uri.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            uris,
+            [
+                format!("{BASE_URL}/meta-data/cam/security-credentials"),
+                format!("{BASE_URL}/meta-data/cam/security-credentials/role-name"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn role_credential_failed() {
+        let transport = MockTransport::new();
+        transport.push_text(
+            StatusCode::OK,
+            HeaderMap::new(),
+            role_credential("Failure", 0),
+        );
+        let provider = CvmRoleProvider::new()
+            .with_base_url(BASE_URL)
+            .with_role("role-name")
+            .with_transport(transport.clone());
+
+        let err = provider.credential().await.unwrap_err();
+
+        assert!(matches!(err, Error::Credential(_)), "{err:?}");
+        assert!(err.to_string().contains("Failure"), "{err}");
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn metadata_status_error() {
+        let transport = MockTransport::new();
+        transport.push_text(StatusCode::NOT_FOUND, HeaderMap::new(), "not found");
+        let provider = CvmRoleProvider::new()
+            .with_base_url(BASE_URL)
+            .with_transport(transport);
+
+        let err = provider.credential().await.unwrap_err();
+
+        assert!(matches!(err, Error::Credential(_)), "{err:?}");
+        assert!(err.to_string().contains("404"), "{err}");
+    }
+
+    /// the metadata service which never responds
+    #[derive(Debug)]
+    struct PendingTransport;
+
+    impl Transport for PendingTransport {
+        fn send(
+            &self,
+            _request: Request<RequestBody>,
+        ) -> BoxFuture<'_, Result<hyper::Response<ResponseBody>, Error>> {
+            pending().boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn metadata_timeout() {
+        let provider = CvmRoleProvider::new()
+            .with_base_url(BASE_URL)
+            .with_timeout(Duration::from_millis(10))
+            .with_transport(PendingTransport);
+
+        assert!(matches!(provider.credential().await, Err(Error::Timeout)));
+    }
+}
@@ -0,0 +1,559 @@
+//! the tccli compatible profile provider
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
+
+use futures_util::future::{ready, BoxFuture};
+use futures_util::FutureExt;
+use serde::Deserialize;
+
+use crate::client::Auth;
+use crate::credential::CredentialProvider;
+use crate::error::Error;
+
+/// the environment variable of the profile name
+pub const ENV_PROFILE: &str = "TENCENTCLOUD_PROFILE";
+
+/// the default profile name
+const DEFAULT_PROFILE: &str = "default";
+
+/// the tccli config dir under the home dir
+const CONFIG_DIR: &str = ".tencentcloud";
+
+/// the shared credentials file name
+const CREDENTIALS_FILE: &str = "credentials";
+
+/// the default interval to check whether the credential files are modified
+const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// read the credential and region from the files written by tccli
+///
+/// the credential is read from `~/.tencentcloud/<profile>.credential` first, then the
+/// `[<profile>]` section of `~/.tencentcloud/credentials`; the region is read from
+/// `~/.tencentcloud/<profile>.configure`
+///
+/// the profile is `default` unless it is set by [`ProfileProvider::with_profile`] or the
+/// `TENCENTCLOUD_PROFILE` environment variable
+///
+/// the loaded credential is cached, the files are read again only when they are modified; the
+/// modification is checked at most once per the check interval, so the files are not touched by
+/// every request
+#[derive(Debug, Clone)]
+pub struct ProfileProvider {
+    profile: String,
+    dir: Option<PathBuf>,
+    check_interval: Duration,
+    cache: Arc<Mutex<Option<Loaded>>>,
+}
+
+/// the credential loaded from the files
+#[derive(Debug)]
+struct Loaded {
+    /// the modified time of the `.credential` and `credentials` files when loading
+    modified: [Option<SystemTime>; 2],
+    /// when the modified time is checked last time
+    checked_at: Instant,
+    auth: Auth,
+}
+
+impl Default for ProfileProvider {
+    fn default() -> Self {
+        let profile = env::var(ENV_PROFILE)
+            .ok()
+            .filter(|profile| !profile.is_empty())
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+
+        Self {
+            profile,
+            dir: None,
+            check_interval: DEFAULT_CHECK_INTERVAL,
+            cache: Default::default(),
+        }
+    }
+}
+
+impl ProfileProvider {
+    /// create the provider
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// use the named `profile`
+    pub fn with_profile(mut self, profile: impl Into<String>) -> Self {
+        self.profile = profile.into();
+        self.cache = Default::default();
+
+        self
+    }
+
+    /// read the files in the `dir` instead of `~/.tencentcloud`
+    pub fn with_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir = Some(dir.into());
+        self.cache = Default::default();
+
+        self
+    }
+
+    /// set the interval to check whether the credential files are modified, the default is 5s
+    pub fn with_check_interval(mut self, check_interval: Duration) -> Self {
+        self.check_interval = check_interval;
+
+        self
+    }
+
+    /// the profile name
+    pub fn profile(&self) -> &str {
+        &self.profile
+    }
+
+    /// the default region of the profile, `None` means the profile doesn't set it
+    pub fn region(&self) -> Result<Option<String>, Error> {
+        let path = self
+            .config_dir()?
+            .join(format!("{}.configure", self.profile));
+        let configure = match read_file(&path)? {
+            None => return Ok(None),
+            Some(content) => serde_json::from_str::<Configure>(&content).map_err(|err| {
+                Error::Credential(format!("parse {} failed: {err}", path.display()).into())
+            })?,
+        };
+
+        Ok(configure
+            .sys_param
+            .and_then(|sys_param| sys_param.region)
+            .filter(|region| !region.is_empty()))
+    }
+
+    /// get the cached credential, or load it if the files are modified since the last loading,
+    /// the modification is not checked again in the check interval
+    fn cached_load(&self) -> Result<Auth, Error> {
+        let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(loaded) = cache
+            .as_ref()
+            .filter(|loaded| loaded.checked_at.elapsed() < self.check_interval)
+        {
+            return Ok(loaded.auth.clone());
+        }
+
+        let dir = self.config_dir()?;
+        let paths = self.credential_paths(&dir);
+        let modified = paths.each_ref().map(|path| modified_time(path));
+        let checked_at = Instant::now();
+
+        if let Some(loaded) = cache.as_mut().filter(|loaded| loaded.modified == modified) {
+            loaded.checked_at = checked_at;
+
+            return Ok(loaded.auth.clone());
+        }
+
+        let auth = self.load(&dir, &paths)?;
+        *cache = Some(Loaded {
+            modified,
+            checked_at,
+            auth: auth.clone(),
+        });
+
+        Ok(auth)
+    }
+
+    /// the `<profile>.credential` and `credentials` files in the `dir`
+    fn credential_paths(&self, dir: &Path) -> [PathBuf; 2] {
+        [
+            dir.join(format!("{}.credential", self.profile)),
+            dir.join(CREDENTIALS_FILE),
+        ]
+    }
+
+    fn load(
+        &self,
+        dir: &Path,
+        [credential_path, credentials_path]: &[PathBuf; 2],
+    ) -> Result<Auth, Error> {
+        let path = credential_path;
+        if let Some(content) = read_file(path)? {
+            let credential = serde_json::from_str::<TccliCredential>(&content).map_err(|err| {
+                Error::Credential(format!("parse {} failed: {err}", path.display()).into())
+            })?;
+
+            let auth = Auth::new(credential.secret_key, credential.secret_id);
+
+            return Ok(match credential.token.filter(|token| !token.is_empty()) {
+                None => auth,
+                Some(token) => auth.with_token(token),
+            });
+        }
+
+        let path = credentials_path;
+        if let Some(content) = read_file(path)? {
+            if let Some(section) = parse_ini(&content).remove(&self.profile) {
+                let get = |key: &str| {
+                    section.get(key).cloned().ok_or_else(|| {
+                        Error::Credential(
+                            format!(
+                                "miss {key} of profile {} in {}",
+                                self.profile,
+                                path.display()
+                            )
+                            .into(),
+                        )
+                    })
+                };
+
+                let auth = Auth::new(get("secret_key")?, get("secret_id")?);
+
+                return Ok(match section.get("token") {
+                    None => auth,
+                    Some(token) => auth.with_token(token.clone()),
+                });
+            }
+        }
+
+        Err(Error::Credential(
+            format!("profile {} not found in {}", self.profile, dir.display()).into(),
+        ))
+    }
+
+    fn config_dir(&self) -> Result<PathBuf, Error> {
+        if let Some(dir) = &self.dir {
+            return Ok(dir.clone());
+        }
+
+        home_dir()
+            .map(|home| home.join(CONFIG_DIR))
+            .ok_or_else(|| Error::Credential("can't find the home dir".into()))
+    }
+}
+
+impl CredentialProvider for ProfileProvider {
+    fn credential(&self) -> BoxFuture<'_, Result<Auth, Error>> {
+        ready(self.cached_load()).boxed()
+    }
+}
+
+/// the `<profile>.credential` file
+#[derive(Debug, Deserialize)]
+struct TccliCredential {
+    #[serde(rename = "secretId")]
+    secret_id: String,
+
+    #[serde(rename = "secretKey")]
+    secret_key: String,
+
+    #[serde(rename = "token")]
+    token: Option<String>,
+}
+
+/// the `<profile>.configure` file
+#[derive(Debug, Deserialize)]
+struct Configure {
+    #[serde(rename = "_sys_param")]
+    sys_param: Option<SysParam>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SysParam {
+    #[serde(rename = "region")]
+    region: Option<String>,
+}
+
+fn home_dir() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
+
+/// the modified time of the file, `None` means the file doesn't exist or the time is unavailable
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// read the file, `None` means the file doesn't exist
+fn read_file(path: &Path) -> Result<Option<String>, Error> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(Error::Credential(
+            format!("read {} failed: {err}", path.display()).into(),
+        )),
+    }
+}
+
+/// parse the ini `content` into sections
+fn parse_ini(content: &str) -> HashMap<String, HashMap<String, String>> {
+    let mut sections = HashMap::<String, HashMap<String, String>>::new();
+    let mut current = None;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        if let Some(name) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            let name = name.trim().to_string();
+            sections.entry(name.clone()).or_default();
+            current = Some(name);
+
+            continue;
+        }
+
+        if let (Some(section), Some((key, value))) = (&current, line.split_once('=')) {
+            sections
+                .entry(section.clone())
+                .or_default()
+                .insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::time::Duration;
+
+    use super::*;
+
+    /// the empty temp dir for the test `name`
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!(
+            "tencentcloud-profile-{}-{name}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    fn credential(secret_id: &str, token: Option<&str>) -> String {
+        serde_json::json!({ "secretId": secret_id, "secretKey": "key", "token": token }).to_string()
+    }
+
+    #[test]
+    fn parse_ini_sections() {
+        let sections = parse_ini(
+            "ignored = before section\n\
+             # comment\n\
+             ; comment\n\
+             [default]\n\
+             secret_id = id\n\
+             \n\
+             \x20 secret_key=key = with equal  \n\
+             [ other ]\n\
+             token=\n\
+             no equal sign\n\
+             [empty]\n",
+        );
+
+        assert_eq!(sections.len(), 3);
+        assert_eq!(sections["default"].len(), 2);
+        assert_eq!(sections["default"]["secret_id"], "id");
+        assert_eq!(sections["default"]["secret_key"], "key = with equal");
+        assert_eq!(sections["other"].len(), 1);
+        assert_eq!(sections["other"]["token"], "");
+        assert!(sections["empty"].is_empty());
+    }
+
+    #[tokio::test]
+    async fn credential_file_takes_precedence() {
+        let dir = temp_dir("precedence");
+        fs::write(
+            dir.join("dev.credential"),
+            credential("from-credential", Some("token")),
+        )
+        .unwrap();
+        fs::write(
+            dir.join(CREDENTIALS_FILE),
+            "[dev]\nsecret_id = from-credentials\nsecret_key = key\n",
+        )
+        .unwrap();
+
+        let auth = ProfileProvider::new()
+            .with_profile("dev")
+            .with_dir(&dir)
+            .credential()
+            .await
+            .unwrap();
+
+        assert_eq!(auth.secret_id(), "from-credential");
+        assert_eq!(auth.token(), Some("token"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn fallback_to_credentials_file() {
+        let dir = temp_dir("fallback");
+        fs::write(dir.join("other.credential"), credential("other", None)).unwrap();
+        fs::write(
+            dir.join(CREDENTIALS_FILE),
+            "[default]\nsecret_id = default\nsecret_key = key\n\
+             [dev]\nsecret_id = dev\nsecret_key = key\ntoken = token\n",
+        )
+        .unwrap();
+
+        let provider = ProfileProvider::new().with_dir(&dir);
+        let auth = provider
+            .clone()
+            .with_profile("dev")
+            .credential()
+            .await
+            .unwrap();
+        assert_eq!(auth.secret_id(), "dev");
+        assert_eq!(auth.token(), Some("token"));
+
+        let auth = provider.with_profile("default").credential().await.unwrap();
+        assert_eq!(auth.secret_id(), "default");
+        assert_eq!(auth.token(), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn missing_profile_or_key() {
+        let dir = temp_dir("missing");
+        fs::write(dir.join(CREDENTIALS_FILE), "[dev]\nsecret_id = dev\n").unwrap();
+        let provider = ProfileProvider::new().with_dir(&dir);
+
+        let err = provider
+            .clone()
+            .with_profile("dev")
+            .credential()
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("miss secret_key"), "{err}");
+
+        let err = provider
+            .with_profile("prod")
+            .credential()
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("profile prod not found"), "{err}");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn reload_when_modified() {
+        let dir = temp_dir("reload");
+        let path = dir.join("default.credential");
+        fs::write(&path, credential("old", None)).unwrap();
+        let provider = ProfileProvider::new()
+            .with_profile("default")
+            .with_dir(&dir)
+            .with_check_interval(Duration::ZERO);
+
+        assert_eq!(provider.credential().await.unwrap().secret_id(), "old");
+
+        // the cached credential is used until the modified time changes
+        let modified = modified_time(&path).unwrap();
+        fs::write(&path, credential("new", None)).unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        assert_eq!(provider.credential().await.unwrap().secret_id(), "old");
+
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified + Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(provider.credential().await.unwrap().secret_id(), "new");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn check_modified_once_per_interval() {
+        let dir = temp_dir("interval");
+        let path = dir.join("default.credential");
+        fs::write(&path, credential("old", None)).unwrap();
+        let provider = ProfileProvider::new()
+            .with_profile("default")
+            .with_dir(&dir)
+            .with_check_interval(Duration::from_secs(3600));
+
+        assert_eq!(provider.credential().await.unwrap().secret_id(), "old");
+
+        // the modification is not checked in the interval
+        let modified = modified_time(&path).unwrap();
+        fs::write(&path, credential("new", None)).unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified + Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(provider.credential().await.unwrap().secret_id(), "old");
+
+        // the clones share the cache
+        let provider = provider.clone().with_check_interval(Duration::ZERO);
+        assert_eq!(provider.credential().await.unwrap().secret_id(), "new");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn region() {
+        let dir = temp_dir("region");
+        let provider = ProfileProvider::new().with_dir(&dir);
+
+        // the profile without the configure file
+        assert_eq!(provider.clone().with_profile("dev").region().unwrap(), None);
+
+        fs::write(
+            dir.join("dev.configure"),
+            r#"{ "_sys_param": { "region": "ap-shanghai", "output": "json" }, "cvm": {} }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            provider
+                .clone()
+                .with_profile("dev")
+                .region()
+                .unwrap()
+                .as_deref(),
+            Some("ap-shanghai")
+        );
+
+        // the empty region is treated as unset
+        fs::write(
+            dir.join("empty.configure"),
+            r#"{ "_sys_param": { "region": "" } }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            provider.clone().with_profile("empty").region().unwrap(),
+            None
+        );
+
+        fs::write(dir.join("none.configure"), "{}").unwrap();
+        assert_eq!(
+            provider.clone().with_profile("none").region().unwrap(),
+            None
+        );
+
+        fs::write(dir.join("invalid.configure"), "not json").unwrap();
+        let err = provider.with_profile("invalid").region().unwrap_err();
+        assert!(err.to_string().contains("invalid.configure"), "{err}");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
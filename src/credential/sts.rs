@@ -0,0 +1,569 @@
+//! the sts assume role providers
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, SystemTime};
+
+use futures_util::future::BoxFuture;
+use futures_util::FutureExt;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use tracing::instrument;
+
+use crate::api::Api;
+use crate::client::{Auth, Client};
+use crate::credential::{CredentialCache, CredentialProvider, DEFAULT_REFRESH_AHEAD};
+use crate::error::Error;
+
+/// the environment variable of the tke region
+pub const ENV_TKE_REGION: &str = "TKE_REGION";
+
+/// the environment variable of the tke oidc provider id
+pub const ENV_TKE_PROVIDER_ID: &str = "TKE_PROVIDER_ID";
+
+/// the environment variable of the tke web identity token file
+pub const ENV_TKE_WEB_IDENTITY_TOKEN_FILE: &str = "TKE_WEB_IDENTITY_TOKEN_FILE";
+
+/// the environment variable of the tke role arn
+pub const ENV_TKE_ROLE_ARN: &str = "TKE_ROLE_ARN";
+
+/// assume the role by the sts `AssumeRole` api
+///
+/// the base [`Client`] provides the identity which assumes the role, the returned temporary
+/// credential is cached and the role is re-assumed before the credential expires
+#[derive(Debug)]
+pub struct AssumeRoleProvider {
+    client: Client,
+    role_arn: String,
+    session_name: String,
+    duration: Option<Duration>,
+    policy: Option<String>,
+    external_id: Option<String>,
+    cache: CredentialCache,
+}
+
+impl AssumeRoleProvider {
+    /// create the provider, the `client` is used to call the sts api
+    pub fn new(
+        client: Client,
+        role_arn: impl Into<String>,
+        session_name: impl Into<String>,
+    ) -> Self {
+        Self {
+            client,
+            role_arn: role_arn.into(),
+            session_name: session_name.into(),
+            duration: None,
+            policy: None,
+            external_id: None,
+            cache: CredentialCache::new(DEFAULT_REFRESH_AHEAD),
+        }
+    }
+
+    /// set the duration of the temporary credential, the default is decided by the sts, which is
+    /// 2 hours currently
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+
+        self
+    }
+
+    /// set the policy to restrict the permissions of the temporary credential
+    pub fn with_policy(mut self, policy: impl Into<String>) -> Self {
+        self.policy = Some(policy.into());
+
+        self
+    }
+
+    /// set the external id of the role
+    pub fn with_external_id(mut self, external_id: impl Into<String>) -> Self {
+        self.external_id = Some(external_id.into());
+
+        self
+    }
+
+    /// set the duration to re-assume the role before the credential expires, the default is 5
+    /// minutes
+    pub fn with_refresh_ahead(mut self, refresh_ahead: Duration) -> Self {
+        self.cache = CredentialCache::new(refresh_ahead);
+
+        self
+    }
+
+    #[instrument(level = "trace", skip(self), err)]
+    async fn assume(&self) -> Result<Auth, Error> {
+        let request = AssumeRoleRequest {
+            role_arn: self.role_arn.clone(),
+            role_session_name: self.session_name.clone(),
+            duration_seconds: self.duration.map(|duration| duration.as_secs()),
+            policy: self.policy.clone(),
+            external_id: self.external_id.clone(),
+        };
+
+        let (response, _) = self.client.send::<AssumeRole>(&request).await?;
+
+        response.into_auth()
+    }
+}
+
+impl CredentialProvider for AssumeRoleProvider {
+    fn credential(&self) -> BoxFuture<'_, Result<Auth, Error>> {
+        self.cache.get_or_refresh(|| self.assume()).boxed()
+    }
+}
+
+/// assume the role by the sts `AssumeRoleWithWebIdentity` api, for the tke oidc
+///
+/// the web identity token file is checked every time the role is assumed, and read again only
+/// when it is modified, so the rotated token is used; the returned temporary credential is
+/// cached and the role is re-assumed before the credential expires
+#[derive(Debug)]
+pub struct WebIdentityProvider {
+    client: Client,
+    provider_id: String,
+    role_arn: String,
+    token_file: PathBuf,
+    session_name: String,
+    duration: Option<Duration>,
+    cache: CredentialCache,
+    token: Mutex<Option<Token>>,
+}
+
+/// the token read from the token file
+#[derive(Debug)]
+struct Token {
+    /// the modified time of the token file when reading
+    modified: SystemTime,
+    token: String,
+}
+
+impl WebIdentityProvider {
+    /// create the provider
+    ///
+    /// the `AssumeRoleWithWebIdentity` api doesn't need signing, so the `client` can be built
+    /// with any credential
+    pub fn new(
+        client: Client,
+        provider_id: impl Into<String>,
+        role_arn: impl Into<String>,
+        token_file: impl Into<PathBuf>,
+    ) -> Self {
+        Self {
+            client,
+            provider_id: provider_id.into(),
+            role_arn: role_arn.into(),
+            token_file: token_file.into(),
+            session_name: default_session_name(),
+            duration: None,
+            cache: CredentialCache::new(DEFAULT_REFRESH_AHEAD),
+            token: Default::default(),
+        }
+    }
+
+    /// create the provider by the environment variables which are injected by the tke
+    ///
+    /// the `TKE_REGION`, `TKE_PROVIDER_ID`, `TKE_WEB_IDENTITY_TOKEN_FILE` and `TKE_ROLE_ARN` are
+    /// required
+    pub fn from_env() -> Result<Self, Error> {
+        let get = |key: &str| {
+            env::var(key)
+                .ok()
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| Error::Credential(format!("miss env {key}").into()))
+        };
+
+        let client = Client::builder()
+            .region(get(ENV_TKE_REGION)?)
+            .auth(Auth::new(String::new(), String::new()))
+            .build()?;
+
+        Ok(Self::new(
+            client,
+            get(ENV_TKE_PROVIDER_ID)?,
+            get(ENV_TKE_ROLE_ARN)?,
+            get(ENV_TKE_WEB_IDENTITY_TOKEN_FILE)?,
+        ))
+    }
+
+    /// set the role session name, the default is `tencentcloud-rs-<timestamp>`
+    pub fn with_session_name(mut self, session_name: impl Into<String>) -> Self {
+        self.session_name = session_name.into();
+
+        self
+    }
+
+    /// set the duration of the temporary credential
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+
+        self
+    }
+
+    /// set the duration to re-assume the role before the credential expires, the default is 5
+    /// minutes
+    pub fn with_refresh_ahead(mut self, refresh_ahead: Duration) -> Self {
+        self.cache = CredentialCache::new(refresh_ahead);
+
+        self
+    }
+
+    #[instrument(level = "trace", skip(self), err)]
+    async fn assume(&self) -> Result<Auth, Error> {
+        let token = self.read_token().map_err(|err| {
+            Error::Credential(
+                format!(
+                    "read web identity token file {} failed: {err}",
+                    self.token_file.display()
+                )
+                .into(),
+            )
+        })?;
+
+        let request = AssumeRoleWithWebIdentityRequest {
+            provider_id: self.provider_id.clone(),
+            web_identity_token: token,
+            role_arn: self.role_arn.clone(),
+            role_session_name: self.session_name.clone(),
+            duration_seconds: self.duration.map(|duration| duration.as_secs()),
+        };
+
+        let (response, _) = self
+            .client
+            .send::<AssumeRoleWithWebIdentity>(&request)
+            .await?;
+
+        response.into_auth()
+    }
+
+    /// get the cached token, or read it if the token file is modified since the last reading
+    fn read_token(&self) -> io::Result<String> {
+        let mut cached = self.token.lock().unwrap_or_else(PoisonError::into_inner);
+        let modified = modified_time(&self.token_file)?;
+        if let Some(cached) = cached.as_ref().filter(|cached| cached.modified == modified) {
+            return Ok(cached.token.clone());
+        }
+
+        let token = fs::read_to_string(&self.token_file)?.trim().to_string();
+        *cached = Some(Token {
+            modified,
+            token: token.clone(),
+        });
+
+        Ok(token)
+    }
+}
+
+impl CredentialProvider for WebIdentityProvider {
+    fn credential(&self) -> BoxFuture<'_, Result<Auth, Error>> {
+        self.cache.get_or_refresh(|| self.assume()).boxed()
+    }
+}
+
+fn modified_time(path: &Path) -> io::Result<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified())
+}
+
+fn default_session_name() -> String {
+    format!(
+        "tencentcloud-rs-{}",
+        OffsetDateTime::now_utc().unix_timestamp()
+    )
+}
+
+#[derive(Debug, Copy, Clone)]
+struct AssumeRole;
+
+impl Api for AssumeRole {
+    type Request = AssumeRoleRequest;
+    type Response = AssumeRoleResponse;
+    const VERSION: &'static str = "2018-08-13";
+    const ACTION: &'static str = "AssumeRole";
+    const SERVICE: &'static str = "sts";
+    const HOST: &'static str = "sts.tencentcloudapi.com";
+}
+
+#[derive(Debug, Copy, Clone)]
+struct AssumeRoleWithWebIdentity;
+
+impl Api for AssumeRoleWithWebIdentity {
+    type Request = AssumeRoleWithWebIdentityRequest;
+    type Response = AssumeRoleResponse;
+    const VERSION: &'static str = "2018-08-13";
+    const ACTION: &'static str = "AssumeRoleWithWebIdentity";
+    const SERVICE: &'static str = "sts";
+    const HOST: &'static str = "sts.tencentcloudapi.com";
+    const SKIP_SIGN: bool = true;
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AssumeRoleRequest {
+    #[serde(rename = "RoleArn")]
+    role_arn: String,
+
+    #[serde(rename = "RoleSessionName")]
+    role_session_name: String,
+
+    #[serde(rename = "DurationSeconds", skip_serializing_if = "Option::is_none")]
+    duration_seconds: Option<u64>,
+
+    #[serde(rename = "Policy", skip_serializing_if = "Option::is_none")]
+    policy: Option<String>,
+
+    #[serde(rename = "ExternalId", skip_serializing_if = "Option::is_none")]
+    external_id: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+struct AssumeRoleWithWebIdentityRequest {
+    #[serde(rename = "ProviderId")]
+    provider_id: String,
+
+    #[serde(rename = "WebIdentityToken")]
+    web_identity_token: String,
+
+    #[serde(rename = "RoleArn")]
+    role_arn: String,
+
+    #[serde(rename = "RoleSessionName")]
+    role_session_name: String,
+
+    #[serde(rename = "DurationSeconds", skip_serializing_if = "Option::is_none")]
+    duration_seconds: Option<u64>,
+}
+
+impl std::fmt::Debug for AssumeRoleWithWebIdentityRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AssumeRoleWithWebIdentityRequest")
+            .field("provider_id", &self.provider_id)
+            .field("role_arn", &self.role_arn)
+            .field("role_session_name", &self.role_session_name)
+            .field("duration_seconds", &self.duration_seconds)
+            .finish_non_exhaustive()
+    }
+}
+
+/// the `AssumeRole` and `AssumeRoleWithWebIdentity` response
+#[derive(Deserialize)]
+struct AssumeRoleResponse {
+    #[serde(rename = "Credentials")]
+    credentials: Credentials,
+
+    #[serde(rename = "ExpiredTime")]
+    expired_time: i64,
+}
+
+impl AssumeRoleResponse {
+    fn into_auth(self) -> Result<Auth, Error> {
+        let expiration = OffsetDateTime::from_unix_timestamp(self.expired_time)
+            .map_err(|err| Error::Credential(err.into()))?;
+
+        Ok(Auth::new(
+            self.credentials.tmp_secret_key,
+            self.credentials.tmp_secret_id,
+        )
+        .with_token(self.credentials.token)
+        .with_expiration(expiration))
+    }
+}
+
+impl std::fmt::Debug for AssumeRoleResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AssumeRoleResponse")
+            .field("tmp_secret_id", &self.credentials.tmp_secret_id)
+            .field("expired_time", &self.expired_time)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Deserialize)]
+struct Credentials {
+    #[serde(rename = "Token")]
+    token: String,
+
+    #[serde(rename = "TmpSecretId")]
+    tmp_secret_id: String,
+
+    #[serde(rename = "TmpSecretKey")]
+    tmp_secret_key: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::test_util::{self, MockTransport};
+
+    /// the `AssumeRole` response whose credential `id` expires in the `expires_in`
+    fn assume_role_response(id: &str, expires_in: Duration) -> Value {
+        json!({
+            "Credentials": {
+                "Token": format!("{id}-token"),
+                "TmpSecretId": id,
+                "TmpSecretKey": format!("{id}-key"),
+            },
+            "ExpiredTime": (OffsetDateTime::now_utc() + expires_in).unix_timestamp(),
+            "Expiration": "2026-10-17T10:00:00Z",
+        })
+    }
+
+    /// the token file of the test `name`
+    fn token_file(name: &str, token: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!(
+            "tencentcloud-sts-{}-{name}.token",
+            std::process::id()
+        ));
+        fs::write(&path, token).unwrap();
+
+        path
+    }
+
+    #[tokio::test]
+    async fn assume_role() {
+        let transport = MockTransport::new();
+        transport.push_ok(assume_role_response("tmp-id", Duration::from_secs(7200)));
+        let client = test_util::builder(transport.clone()).build().unwrap();
+        let provider = AssumeRoleProvider::new(client, "qcs::cam::uin/1:roleName/role", "session")
+            .with_duration(Duration::from_secs(3600))
+            .with_policy("{}")
+            .with_external_id("external-id");
+
+        let auth = provider.credential().await.unwrap();
+        assert_eq!(auth.secret_id(), "tmp-id");
+        assert_eq!(auth.secret_key(), "tmp-id-key");
+        assert_eq!(auth.token(), Some("tmp-id-token"));
+
+        let request = &transport.requests()[0];
+        assert_eq!(request.headers["X-TC-Action"], "AssumeRole");
+        assert_eq!(request.headers["X-TC-Version"], "2018-08-13");
+        assert_eq!(request.uri.host(), Some("sts.tencentcloudapi.com"));
+        assert!(request.headers["Authorization"]
+            .to_str()
+            .unwrap()
+            .starts_with("TC3-HMAC-SHA256 Credential=id/"));
+        assert_eq!(
+            request.body,
+            json!({
+                "RoleArn": "qcs::cam::uin/1:roleName/role",
+                "RoleSessionName": "session",
+                "DurationSeconds": 3600,
+                "Policy": "{}",
+                "ExternalId": "external-id",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn re_assume_near_expiry() {
+        let transport = MockTransport::new();
+        transport
+            .push_ok(assume_role_response("first", Duration::from_secs(2 * 60)))
+            .push_ok(assume_role_response("second", Duration::from_secs(7200)));
+        let client = test_util::builder(transport.clone()).build().unwrap();
+        let provider = AssumeRoleProvider::new(client, "role", "session");
+
+        // the first credential expires in the default refresh ahead duration
+        assert_eq!(provider.credential().await.unwrap().secret_id(), "first");
+        assert_eq!(provider.credential().await.unwrap().secret_id(), "second");
+        assert_eq!(provider.credential().await.unwrap().secret_id(), "second");
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            requests[0].body,
+            json!({ "RoleArn": "role", "RoleSessionName": "session" })
+        );
+    }
+
+    #[tokio::test]
+    async fn assume_role_with_web_identity() {
+        let path = token_file("web-identity", "first-token\n");
+        let transport = MockTransport::new();
+        transport
+            .push_ok(assume_role_response("first", Duration::from_secs(2 * 60)))
+            .push_ok(assume_role_response("second", Duration::from_secs(7200)));
+        let client = test_util::builder(transport.clone()).build().unwrap();
+        let provider = WebIdentityProvider::new(client, "provider-id", "role", &path)
+            .with_session_name("session")
+            .with_duration(Duration::from_secs(3600));
+
+        assert_eq!(provider.credential().await.unwrap().secret_id(), "first");
+
+        // the rotated token is read when the role is re-assumed
+        fs::write(&path, "second-token").unwrap();
+        touch(&path, SystemTime::now() + Duration::from_secs(1));
+        assert_eq!(provider.credential().await.unwrap().secret_id(), "second");
+
+        let requests = transport.requests();
+        for request in &requests {
+            assert_eq!(request.headers["X-TC-Action"], "AssumeRoleWithWebIdentity");
+            assert_eq!(request.headers["Authorization"], "SKIP");
+            assert!(!request.headers.contains_key("X-TC-Token"));
+        }
+        assert_eq!(
+            requests[0].body,
+            json!({
+                "ProviderId": "provider-id",
+                "WebIdentityToken": "first-token",
+                "RoleArn": "role",
+                "RoleSessionName": "session",
+                "DurationSeconds": 3600,
+            })
+        );
+        assert_eq!(requests[1].body["WebIdentityToken"], "second-token");
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    /// set the modified time of the file, the rewritten file may keep the modified time on the
+    /// file system whose time resolution is coarse
+    fn touch(path: &Path, modified: SystemTime) {
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    #[test]
+    fn web_identity_token_cached_by_modified_time() {
+        let path = token_file("token-cache", "first-token\n");
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        let client = test_util::builder(MockTransport::new()).build().unwrap();
+        let provider = WebIdentityProvider::new(client, "provider-id", "role", &path);
+
+        assert_eq!(provider.read_token().unwrap(), "first-token");
+
+        // the file is not read again if it is not modified
+        fs::write(&path, "second-token").unwrap();
+        touch(&path, modified);
+        assert_eq!(provider.read_token().unwrap(), "first-token");
+
+        touch(&path, modified + Duration::from_secs(1));
+        assert_eq!(provider.read_token().unwrap(), "second-token");
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn web_identity_token_file_missing() {
+        let transport = MockTransport::new();
+        let client = test_util::builder(transport.clone()).build().unwrap();
+        let provider = WebIdentityProvider::new(
+            client,
+            "provider-id",
+            "role",
+            env::temp_dir().join("tencentcloud-sts-missing.token"),
+        );
+
+        let err = provider.credential().await.unwrap_err();
+
+        assert!(matches!(err, Error::Credential(_)), "{err:?}");
+        assert!(err.to_string().contains("web identity token file"), "{err}");
+        assert!(transport.requests().is_empty());
+    }
+}
@@ -0,0 +1,267 @@
+//! tencentcloud api endpoint
+//!
+//! the [`EndpointResolver`] decides which host the [`Client`](crate::Client) sends the request to,
+//! the [`DefaultEndpointResolver`] supports the global, regional, internal and custom endpoints
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use hyper::Uri;
+
+use crate::error::Error;
+
+/// the tencentcloud api root domain
+const ROOT_DOMAIN: &str = "tencentcloudapi.com";
+
+/// tencentcloud api endpoint
+///
+/// the endpoint decides where the request is sent to, the `host` and `path` are also used to sign
+/// the request
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Endpoint {
+    /// the endpoint scheme, `https` or `http`
+    pub scheme: String,
+
+    /// the endpoint host, may contain the port, for example: `tmt.tencentcloudapi.com`
+    pub host: String,
+
+    /// the endpoint path, for example: `/`
+    pub path: String,
+}
+
+impl Endpoint {
+    /// the request uri of the endpoint
+    pub fn uri(&self) -> String {
+        format!("{}://{}{}", self.scheme, self.host, self.path)
+    }
+}
+
+impl FromStr for Endpoint {
+    type Err = Error;
+
+    /// parse the endpoint from an uri, for example: `http://127.0.0.1:8080`
+    ///
+    /// the path is `/` if the uri doesn't contain it
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let uri = s
+            .parse::<Uri>()
+            .map_err(|err| Error::Other(format!("invalid endpoint {s}: {err}").into()))?;
+
+        let scheme = match uri.scheme_str() {
+            Some(scheme @ ("http" | "https")) => scheme.to_string(),
+            _ => return Err(Error::Other(format!("invalid endpoint scheme: {s}").into())),
+        };
+        let host = match uri.authority() {
+            None => return Err(Error::Other(format!("miss endpoint host: {s}").into())),
+            Some(authority) => authority.to_string(),
+        };
+        let path = match uri.path() {
+            "" => "/".to_string(),
+            path => path.to_string(),
+        };
+
+        Ok(Self { scheme, host, path })
+    }
+}
+
+/// resolve the endpoint of the api
+pub trait EndpointResolver: Debug + Send + Sync {
+    /// resolve the endpoint of the api `service` in the `region`
+    ///
+    /// `default` is the endpoint defined by the [`Api`](crate::api::Api)
+    fn resolve(&self, service: &str, region: &str, default: &Endpoint) -> Endpoint;
+}
+
+/// the endpoint host form
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum EndpointMode {
+    /// use the [`Api::HOST`](crate::api::Api::HOST), for example: `cvm.tencentcloudapi.com`
+    #[default]
+    Global,
+
+    /// use the regional host, for example: `cvm.ap-guangzhou.tencentcloudapi.com`
+    Regional,
+
+    /// use the internal host which can be reached inside the vpc, for example:
+    /// `cvm.internal.tencentcloudapi.com`
+    Internal,
+}
+
+/// the default [`EndpointResolver`]
+///
+/// it resolves the endpoint by the [`EndpointMode`], the user overrides have higher priority
+#[derive(Debug, Clone, Default)]
+pub struct DefaultEndpointResolver {
+    mode: EndpointMode,
+    service_overrides: HashMap<String, Endpoint>,
+    global_override: Option<Endpoint>,
+}
+
+impl DefaultEndpointResolver {
+    /// create the resolver with the endpoint `mode`
+    pub fn new(mode: EndpointMode) -> Self {
+        Self {
+            mode,
+            ..Default::default()
+        }
+    }
+
+    /// create the resolver which sends all requests to the `endpoint`
+    ///
+    /// it is useful when using a local stand-in server, such as `http://127.0.0.1:8080`
+    pub fn custom(endpoint: Endpoint) -> Self {
+        Self {
+            global_override: Some(endpoint),
+            ..Default::default()
+        }
+    }
+
+    /// override the endpoint of the api `service`
+    pub fn with_service_endpoint(mut self, service: impl Into<String>, endpoint: Endpoint) -> Self {
+        self.service_overrides.insert(service.into(), endpoint);
+
+        self
+    }
+}
+
+impl EndpointResolver for DefaultEndpointResolver {
+    fn resolve(&self, service: &str, region: &str, default: &Endpoint) -> Endpoint {
+        if let Some(endpoint) = self.service_overrides.get(service) {
+            return endpoint.clone();
+        }
+        if let Some(endpoint) = &self.global_override {
+            return endpoint.clone();
+        }
+
+        let host = match self.mode {
+            EndpointMode::Global => return default.clone(),
+            // some apis are global only, fallback to the default host
+            EndpointMode::Regional if region.is_empty() => return default.clone(),
+            EndpointMode::Regional => format!("{service}.{region}.{ROOT_DOMAIN}"),
+            EndpointMode::Internal => format!("{service}.internal.{ROOT_DOMAIN}"),
+        };
+
+        Endpoint {
+            scheme: default.scheme.clone(),
+            host,
+            path: default.path.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoint(scheme: &str, host: &str, path: &str) -> Endpoint {
+        Endpoint {
+            scheme: scheme.to_string(),
+            host: host.to_string(),
+            path: path.to_string(),
+        }
+    }
+
+    #[test]
+    fn parse_endpoint() {
+        let cases = [
+            (
+                "http://127.0.0.1:8080",
+                endpoint("http", "127.0.0.1:8080", "/"),
+            ),
+            (
+                "https://cvm.tencentcloudapi.com/",
+                endpoint("https", "cvm.tencentcloudapi.com", "/"),
+            ),
+            (
+                "https://proxy.example.com/tc/api",
+                endpoint("https", "proxy.example.com", "/tc/api"),
+            ),
+        ];
+
+        for (s, expected) in cases {
+            let endpoint = s.parse::<Endpoint>().unwrap();
+
+            assert_eq!(endpoint, expected, "endpoint: {s}");
+        }
+
+        assert_eq!(
+            "http://127.0.0.1:8080".parse::<Endpoint>().unwrap().uri(),
+            "http://127.0.0.1:8080/"
+        );
+    }
+
+    #[test]
+    fn parse_invalid_endpoint() {
+        for s in [
+            "ftp://cvm.tencentcloudapi.com",
+            "cvm.tencentcloudapi.com",
+            "/path",
+            "http://",
+            "",
+        ] {
+            assert!(s.parse::<Endpoint>().is_err(), "endpoint: {s}");
+        }
+    }
+
+    #[test]
+    fn resolve_by_mode() {
+        let default = endpoint("https", "cvm.tencentcloudapi.com", "/");
+        let cases = [
+            (
+                EndpointMode::Global,
+                "ap-guangzhou",
+                "cvm.tencentcloudapi.com",
+            ),
+            (
+                EndpointMode::Regional,
+                "ap-guangzhou",
+                "cvm.ap-guangzhou.tencentcloudapi.com",
+            ),
+            (EndpointMode::Regional, "", "cvm.tencentcloudapi.com"),
+            (
+                EndpointMode::Internal,
+                "ap-guangzhou",
+                "cvm.internal.tencentcloudapi.com",
+            ),
+            (
+                EndpointMode::Internal,
+                "",
+                "cvm.internal.tencentcloudapi.com",
+            ),
+        ];
+
+        for (mode, region, host) in cases {
+            let resolved = DefaultEndpointResolver::new(mode).resolve("cvm", region, &default);
+
+            assert_eq!(
+                resolved,
+                endpoint("https", host, "/"),
+                "mode: {mode:?}, region: {region}"
+            );
+        }
+    }
+
+    #[test]
+    fn resolve_overrides() {
+        let default = endpoint("https", "cvm.tencentcloudapi.com", "/");
+        let custom = endpoint("http", "127.0.0.1:8080", "/");
+        let cvm = endpoint("http", "127.0.0.1:9090", "/cvm");
+
+        let resolver = DefaultEndpointResolver::custom(custom.clone());
+        assert_eq!(resolver.resolve("cvm", "ap-guangzhou", &default), custom);
+
+        // the service override has higher priority than the global override
+        let resolver = resolver.with_service_endpoint("cvm", cvm.clone());
+        assert_eq!(resolver.resolve("cvm", "ap-guangzhou", &default), cvm);
+        assert_eq!(resolver.resolve("cbs", "ap-guangzhou", &default), custom);
+
+        let resolver = DefaultEndpointResolver::new(EndpointMode::Regional)
+            .with_service_endpoint("cvm", cvm.clone());
+        assert_eq!(resolver.resolve("cvm", "ap-guangzhou", &default), cvm);
+        assert_eq!(
+            resolver.resolve("cbs", "ap-guangzhou", &default).host,
+            "cbs.ap-guangzhou.tencentcloudapi.com"
+        );
+    }
+}
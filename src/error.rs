@@ -1,12 +1,16 @@
 //! error types
 
 use std::error;
+use std::fmt::{Display, Formatter};
 
+use hyper::body::Bytes;
+use hyper::{HeaderMap, StatusCode};
 use serde::Deserialize;
 use thiserror::Error;
 
 /// the error
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum Error {
     /// tencentcloud api error
     #[error("error: {err}, request id: {request_id}")]
@@ -16,15 +20,47 @@ pub enum Error {
     #[error(transparent)]
     Http(#[from] hyper::Error),
 
+    /// the [`Transport`](crate::transport::Transport) can't establish the connection, or the
+    /// connection is closed before the response is received, the request is retried
+    #[error("connect error: {0}")]
+    Connect(Box<dyn error::Error + Send + Sync + 'static>),
+
+    /// the http status of the response is not OK
+    #[error(transparent)]
+    HttpStatus(Box<HttpStatusError>),
+
     /// other error
     #[error(transparent)]
     Other(Box<dyn error::Error + Send + Sync + 'static>),
 
+    /// get the credential failed
+    #[error("credential error: {0}")]
+    Credential(Box<dyn error::Error + Send + Sync + 'static>),
+
+    /// the request is not completed before the timeout
+    #[error("request timeout")]
+    Timeout,
+
+    /// the waited resource reaches the failure state
+    #[error("wait failure: {0}")]
+    WaitFailure(String),
+
     /// json marshal/unmarshal error
     #[error(transparent)]
     Json(#[from] serde_json::Error),
 }
 
+impl Error {
+    /// the tencentcloud api error code, `None` if the error is not returned by the api server
+    pub fn code(&self) -> Option<&ErrorCode> {
+        match self {
+            Error::Api { err, .. } => Some(&err.code),
+            Error::HttpStatus(err) => err.api_error.as_ref().map(|err| &err.code),
+            _ => None,
+        }
+    }
+}
+
 /// tencentcloud api error
 ///
 /// the code and message are returned by tencentcloud api server
@@ -34,9 +70,521 @@ pub enum Error {
 pub struct ApiError {
     /// the error code
     #[serde(rename = "Code")]
-    pub code: String,
+    pub code: ErrorCode,
 
     /// the error message
     #[serde(rename = "Message")]
     pub message: String,
 }
+
+/// the response whose http status is not OK
+///
+/// only the selected headers and the first bytes of the body are kept, if the body contains the
+/// tencentcloud error envelope, the request id and the [`ApiError`] are parsed from it
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub struct HttpStatusError {
+    /// the http status
+    pub status: StatusCode,
+
+    /// the selected response headers, such as `Content-Type`, `Date`, `Server` and `Retry-After`
+    pub headers: HeaderMap,
+
+    /// the body snippet, at most [`HttpStatusError::BODY_SNIPPET_LIMIT`] bytes
+    pub body: Bytes,
+
+    /// whether the body is truncated
+    pub truncated: bool,
+
+    /// the request id parsed from the body
+    pub request_id: Option<String>,
+
+    /// the tencentcloud api error parsed from the body
+    pub api_error: Option<ApiError>,
+}
+
+impl HttpStatusError {
+    /// the max length of the body snippet
+    pub const BODY_SNIPPET_LIMIT: usize = 4 * 1024;
+}
+
+impl Display for HttpStatusError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "status code is not OK: {}", self.status)?;
+
+        if let Some(err) = &self.api_error {
+            write!(f, ", error: {err}")?;
+        }
+        if let Some(request_id) = &self.request_id {
+            write!(f, ", request id: {request_id}")?;
+        }
+        if self.api_error.is_none() && !self.body.is_empty() {
+            write!(f, ", body: {}", String::from_utf8_lossy(&self.body))?;
+            if self.truncated {
+                f.write_str("...")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// tencentcloud api error code
+///
+/// the code is a dotted path, the first segment is the [`CommonError`], the rest is the
+/// service-specific subcode, for example: `ResourceNotFound.InstanceNotFound`
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize)]
+#[serde(from = "String")]
+pub struct ErrorCode {
+    code: String,
+    kind: CommonError,
+}
+
+impl ErrorCode {
+    /// the raw error code
+    pub fn as_str(&self) -> &str {
+        &self.code
+    }
+
+    /// the common error
+    pub fn kind(&self) -> CommonError {
+        self.kind
+    }
+
+    /// the subcode after the common error, for example: the subcode of
+    /// `ResourceNotFound.Instance.NotFound` is `Instance.NotFound`
+    pub fn subcode(&self) -> Option<&str> {
+        self.code.split_once('.').map(|(_, subcode)| subcode)
+    }
+
+    /// whether the error is transient and the request can be retried
+    ///
+    /// `RequestLimitExceeded*`, `InternalError*`, `ResourceUnavailable*`, `ServiceUnavailable*`
+    /// and `AuthFailure.SignatureExpire` are retryable
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self.kind,
+            CommonError::RequestLimitExceeded
+                | CommonError::InternalError
+                | CommonError::ResourceUnavailable
+                | CommonError::ServiceUnavailable
+                | CommonError::AuthFailure(AuthFailure::SignatureExpire)
+        )
+    }
+
+    /// whether the error is caused by the authentication or authorization, `AuthFailure*` and
+    /// `UnauthorizedOperation*`
+    pub fn is_auth(&self) -> bool {
+        matches!(
+            self.kind,
+            CommonError::AuthFailure(_) | CommonError::UnauthorizedOperation
+        )
+    }
+
+    /// whether the request is throttled, `RequestLimitExceeded*`
+    pub fn is_throttle(&self) -> bool {
+        self.kind == CommonError::RequestLimitExceeded
+    }
+
+    /// whether the resource is not found, `ResourceNotFound*`
+    pub fn is_not_found(&self) -> bool {
+        self.kind == CommonError::ResourceNotFound
+    }
+
+    /// whether the request parameter is invalid, `InvalidParameter*`, `InvalidParameterValue*`,
+    /// `MissingParameter*` and `UnknownParameter*`
+    pub fn is_invalid_parameter(&self) -> bool {
+        matches!(
+            self.kind,
+            CommonError::InvalidParameter
+                | CommonError::InvalidParameterValue
+                | CommonError::MissingParameter
+                | CommonError::UnknownParameter
+        )
+    }
+}
+
+impl From<String> for ErrorCode {
+    fn from(code: String) -> Self {
+        let kind = CommonError::parse(&code);
+
+        Self { code, kind }
+    }
+}
+
+impl From<&str> for ErrorCode {
+    fn from(code: &str) -> Self {
+        code.to_string().into()
+    }
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.code)
+    }
+}
+
+impl PartialEq<str> for ErrorCode {
+    fn eq(&self, other: &str) -> bool {
+        self.code == other
+    }
+}
+
+impl PartialEq<&str> for ErrorCode {
+    fn eq(&self, other: &&str) -> bool {
+        self.code == *other
+    }
+}
+
+/// the common error of tencentcloud api, the first segment of the [`ErrorCode`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum CommonError {
+    /// `ActionOffline`
+    ActionOffline,
+
+    /// `AuthFailure.*`
+    AuthFailure(AuthFailure),
+
+    /// `DryRunOperation`
+    DryRunOperation,
+
+    /// `FailedOperation*`
+    FailedOperation,
+
+    /// `InternalError*`
+    InternalError,
+
+    /// `InvalidAction`
+    InvalidAction,
+
+    /// `InvalidParameter*`
+    InvalidParameter,
+
+    /// `InvalidParameterValue*`
+    InvalidParameterValue,
+
+    /// `InvalidRequest`
+    InvalidRequest,
+
+    /// `IpInBlacklist`
+    IpInBlacklist,
+
+    /// `IpNotInWhitelist`
+    IpNotInWhitelist,
+
+    /// `LimitExceeded*`
+    LimitExceeded,
+
+    /// `MissingParameter*`
+    MissingParameter,
+
+    /// `NoSuchProduct`
+    NoSuchProduct,
+
+    /// `NoSuchVersion`
+    NoSuchVersion,
+
+    /// `OperationDenied*`
+    OperationDenied,
+
+    /// `RequestLimitExceeded*`
+    RequestLimitExceeded,
+
+    /// `RequestSizeLimitExceeded`
+    RequestSizeLimitExceeded,
+
+    /// `ResourceInUse*`
+    ResourceInUse,
+
+    /// `ResourceInsufficient*`
+    ResourceInsufficient,
+
+    /// `ResourceNotFound*`
+    ResourceNotFound,
+
+    /// `ResourceUnavailable*`
+    ResourceUnavailable,
+
+    /// `ResponseSizeLimitExceeded`
+    ResponseSizeLimitExceeded,
+
+    /// `ServiceUnavailable*`
+    ServiceUnavailable,
+
+    /// `UnauthorizedOperation*`
+    UnauthorizedOperation,
+
+    /// `UnknownParameter*`
+    UnknownParameter,
+
+    /// `UnsupportedOperation*`
+    UnsupportedOperation,
+
+    /// `UnsupportedProtocol`
+    UnsupportedProtocol,
+
+    /// `UnsupportedRegion`
+    UnsupportedRegion,
+
+    /// the service-specific error which is not a common error
+    Other,
+}
+
+impl CommonError {
+    fn parse(code: &str) -> Self {
+        let (head, subcode) = match code.split_once('.') {
+            None => (code, None),
+            Some((head, subcode)) => (head, Some(subcode)),
+        };
+
+        match head {
+            "ActionOffline" => CommonError::ActionOffline,
+            "AuthFailure" => CommonError::AuthFailure(AuthFailure::parse(subcode)),
+            "DryRunOperation" => CommonError::DryRunOperation,
+            "FailedOperation" => CommonError::FailedOperation,
+            "InternalError" => CommonError::InternalError,
+            "InvalidAction" => CommonError::InvalidAction,
+            "InvalidParameter" => CommonError::InvalidParameter,
+            "InvalidParameterValue" => CommonError::InvalidParameterValue,
+            "InvalidRequest" => CommonError::InvalidRequest,
+            "IpInBlacklist" => CommonError::IpInBlacklist,
+            "IpNotInWhitelist" => CommonError::IpNotInWhitelist,
+            "LimitExceeded" => CommonError::LimitExceeded,
+            "MissingParameter" => CommonError::MissingParameter,
+            "NoSuchProduct" => CommonError::NoSuchProduct,
+            "NoSuchVersion" => CommonError::NoSuchVersion,
+            "OperationDenied" => CommonError::OperationDenied,
+            "RequestLimitExceeded" => CommonError::RequestLimitExceeded,
+            "RequestSizeLimitExceeded" => CommonError::RequestSizeLimitExceeded,
+            "ResourceInUse" => CommonError::ResourceInUse,
+            "ResourceInsufficient" => CommonError::ResourceInsufficient,
+            "ResourceNotFound" => CommonError::ResourceNotFound,
+            "ResourceUnavailable" => CommonError::ResourceUnavailable,
+            "ResponseSizeLimitExceeded" => CommonError::ResponseSizeLimitExceeded,
+            "ServiceUnavailable" => CommonError::ServiceUnavailable,
+            "UnauthorizedOperation" => CommonError::UnauthorizedOperation,
+            "UnknownParameter" => CommonError::UnknownParameter,
+            "UnsupportedOperation" => CommonError::UnsupportedOperation,
+            "UnsupportedProtocol" => CommonError::UnsupportedProtocol,
+            "UnsupportedRegion" => CommonError::UnsupportedRegion,
+            _ => CommonError::Other,
+        }
+    }
+}
+
+/// the `AuthFailure.*` errors
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum AuthFailure {
+    /// `AuthFailure.InvalidAuthorization`
+    InvalidAuthorization,
+
+    /// `AuthFailure.InvalidSecretId`
+    InvalidSecretId,
+
+    /// `AuthFailure.MFAFailure`
+    MfaFailure,
+
+    /// `AuthFailure.SecretIdNotFound`
+    SecretIdNotFound,
+
+    /// `AuthFailure.SignatureExpire`
+    SignatureExpire,
+
+    /// `AuthFailure.SignatureFailure`
+    SignatureFailure,
+
+    /// `AuthFailure.TokenFailure`
+    TokenFailure,
+
+    /// `AuthFailure.UnauthorizedOperation`
+    UnauthorizedOperation,
+
+    /// other `AuthFailure` error
+    Other,
+}
+
+impl AuthFailure {
+    fn parse(subcode: Option<&str>) -> Self {
+        let subcode = subcode.map(|subcode| subcode.split('.').next().unwrap_or(subcode));
+
+        match subcode {
+            Some("InvalidAuthorization") => AuthFailure::InvalidAuthorization,
+            Some("InvalidSecretId") => AuthFailure::InvalidSecretId,
+            Some("MFAFailure") => AuthFailure::MfaFailure,
+            Some("SecretIdNotFound") => AuthFailure::SecretIdNotFound,
+            Some("SignatureExpire") => AuthFailure::SignatureExpire,
+            Some("SignatureFailure") => AuthFailure::SignatureFailure,
+            Some("TokenFailure") => AuthFailure::TokenFailure,
+            Some("UnauthorizedOperation") => AuthFailure::UnauthorizedOperation,
+            _ => AuthFailure::Other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_common_error() {
+        let cases = [
+            ("ActionOffline", CommonError::ActionOffline),
+            ("FailedOperation", CommonError::FailedOperation),
+            (
+                "FailedOperation.InstanceStateNotSupport",
+                CommonError::FailedOperation,
+            ),
+            ("InternalError", CommonError::InternalError),
+            ("InternalError.DbError", CommonError::InternalError),
+            ("InvalidParameter.Malformed", CommonError::InvalidParameter),
+            (
+                "InvalidParameterValue.Range",
+                CommonError::InvalidParameterValue,
+            ),
+            ("LimitExceeded.Quota", CommonError::LimitExceeded),
+            ("RequestLimitExceeded", CommonError::RequestLimitExceeded),
+            (
+                "RequestLimitExceeded.UinLimitExceeded",
+                CommonError::RequestLimitExceeded,
+            ),
+            ("ResourceNotFound.Instance", CommonError::ResourceNotFound),
+            ("ResourceUnavailable", CommonError::ResourceUnavailable),
+            ("ServiceUnavailable", CommonError::ServiceUnavailable),
+            ("UnauthorizedOperation", CommonError::UnauthorizedOperation),
+            ("UnsupportedRegion", CommonError::UnsupportedRegion),
+            ("InstanceNotFound", CommonError::Other),
+            ("internalerror", CommonError::Other),
+            ("InternalErrorX", CommonError::Other),
+            ("", CommonError::Other),
+        ];
+
+        for (code, kind) in cases {
+            assert_eq!(ErrorCode::from(code).kind(), kind, "code: {code}");
+        }
+    }
+
+    #[test]
+    fn parse_auth_failure() {
+        let cases = [
+            ("AuthFailure", AuthFailure::Other),
+            ("AuthFailure.InvalidSecretId", AuthFailure::InvalidSecretId),
+            ("AuthFailure.MFAFailure", AuthFailure::MfaFailure),
+            (
+                "AuthFailure.SecretIdNotFound",
+                AuthFailure::SecretIdNotFound,
+            ),
+            ("AuthFailure.SignatureExpire", AuthFailure::SignatureExpire),
+            (
+                "AuthFailure.SignatureExpire.Detail",
+                AuthFailure::SignatureExpire,
+            ),
+            (
+                "AuthFailure.SignatureFailure",
+                AuthFailure::SignatureFailure,
+            ),
+            ("AuthFailure.TokenFailure", AuthFailure::TokenFailure),
+            ("AuthFailure.Unknown", AuthFailure::Other),
+        ];
+
+        for (code, auth_failure) in cases {
+            assert_eq!(
+                ErrorCode::from(code).kind(),
+                CommonError::AuthFailure(auth_failure),
+                "code: {code}"
+            );
+        }
+    }
+
+    #[test]
+    fn subcode() {
+        let cases = [
+            ("InternalError", None),
+            (
+                "ResourceNotFound.InstanceNotFound",
+                Some("InstanceNotFound"),
+            ),
+            (
+                "ResourceNotFound.Instance.NotFound",
+                Some("Instance.NotFound"),
+            ),
+            ("ResourceNotFound.", Some("")),
+        ];
+
+        for (code, subcode) in cases {
+            assert_eq!(ErrorCode::from(code).subcode(), subcode, "code: {code}");
+        }
+    }
+
+    #[test]
+    fn predicates() {
+        // code, retryable, auth, throttle, not found, invalid parameter
+        let cases = [
+            ("InternalError", true, false, false, false, false),
+            ("InternalError.DbError", true, false, false, false, false),
+            ("RequestLimitExceeded", true, false, true, false, false),
+            ("ResourceUnavailable.Busy", true, false, false, false, false),
+            ("ServiceUnavailable", true, false, false, false, false),
+            (
+                "AuthFailure.SignatureExpire",
+                true,
+                true,
+                false,
+                false,
+                false,
+            ),
+            (
+                "AuthFailure.SignatureFailure",
+                false,
+                true,
+                false,
+                false,
+                false,
+            ),
+            ("UnauthorizedOperation", false, true, false, false, false),
+            ("ResourceNotFound", false, false, false, true, false),
+            ("InvalidParameter", false, false, false, false, true),
+            (
+                "InvalidParameterValue.Range",
+                false,
+                false,
+                false,
+                false,
+                true,
+            ),
+            ("MissingParameter", false, false, false, false, true),
+            ("UnknownParameter", false, false, false, false, true),
+            ("LimitExceeded", false, false, false, false, false),
+            ("FailedOperation", false, false, false, false, false),
+            ("InstanceNotFound", false, false, false, false, false),
+        ];
+
+        for (code, retryable, auth, throttle, not_found, invalid_parameter) in cases {
+            let code = ErrorCode::from(code);
+
+            assert_eq!(code.is_retryable(), retryable, "retryable: {code}");
+            assert_eq!(code.is_auth(), auth, "auth: {code}");
+            assert_eq!(code.is_throttle(), throttle, "throttle: {code}");
+            assert_eq!(code.is_not_found(), not_found, "not found: {code}");
+            assert_eq!(
+                code.is_invalid_parameter(),
+                invalid_parameter,
+                "invalid parameter: {code}"
+            );
+        }
+    }
+
+    #[test]
+    fn deserialize_api_error() {
+        let err = serde_json::from_str::<ApiError>(
+            r#"{"Code":"ResourceNotFound.InstanceNotFound","Message":"not found"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(err.code, "ResourceNotFound.InstanceNotFound");
+        assert!(err.code.is_not_found());
+        assert_eq!(err.code.subcode(), Some("InstanceNotFound"));
+        assert_eq!(err.message, "not found");
+    }
+}
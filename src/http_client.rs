@@ -1,87 +1,695 @@
-#[cfg(all(
-    feature = "tokio-rustls-tls",
-    not(feature = "async-std-rustls-tls"),
-    not(feature = "async-std-native-tls")
-))]
-use hyper::client::HttpConnector;
+//! the built-in transports, the hyper client with the connector of every tls backend
+
+use std::sync::Arc;
+
+#[cfg(feature = "tokio-rustls-tls")]
+use hyper_rustls::HttpsConnector;
 #[cfg(any(
     feature = "tokio-rustls-tls",
     feature = "tokio-native-tls",
     feature = "async-std-rustls-tls",
     feature = "async-std-native-tls"
 ))]
-use hyper::Body;
-#[cfg(all(
+use hyper_util::client::legacy::Client;
+#[cfg(any(feature = "tokio-rustls-tls", feature = "tokio-native-tls"))]
+use hyper_util::rt::{TokioExecutor, TokioTimer};
+
+use crate::error::Error;
+#[cfg(any(
     feature = "tokio-rustls-tls",
-    not(feature = "async-std-rustls-tls"),
-    not(feature = "async-std-native-tls")
+    feature = "tokio-native-tls",
+    feature = "async-std-rustls-tls",
+    feature = "async-std-native-tls"
 ))]
-use hyper_rustls::HttpsConnector;
+use crate::transport::RequestBody;
+use crate::transport::{Transport, TransportConfig};
 
-#[cfg(all(
-    any(feature = "async-std-rustls-tls", feature = "async-std-native-tls"),
-    not(feature = "tokio-rustls-tls"),
-    not(feature = "tokio-native-tls")
-))]
-use crate::async_std_compat::{Connector, HyperExecutor};
+/// define the transport which wraps the hyper client created by the `$new_client`
+macro_rules! http_transport {
+    ($(#[$doc:meta])* $feature:literal, $name:ident, $connector:ty, $new_client:ident) => {
+        $(#[$doc])*
+        #[cfg(feature = $feature)]
+        #[derive(Debug, Clone)]
+        pub struct $name(Client<$connector, RequestBody>);
+
+        #[cfg(feature = $feature)]
+        impl $name {
+            /// create the transport with the `config`
+            pub fn new(config: &TransportConfig) -> Result<Self, Error> {
+                $new_client(config).map(Self)
+            }
+        }
 
+        #[cfg(feature = $feature)]
+        impl Transport for $name {
+            fn send(
+                &self,
+                request: hyper::Request<RequestBody>,
+            ) -> futures_util::future::BoxFuture<
+                '_,
+                Result<hyper::Response<crate::transport::ResponseBody>, Error>,
+            > {
+                self.0.send(request)
+            }
+        }
+    };
+}
+
+http_transport!(
+    /// the tokio transport with the rustls, it is enabled by the `tokio-rustls-tls` feature
+    "tokio-rustls-tls",
+    TokioRustlsTransport,
+    TimeoutConnector<HttpsConnector<ProxyConnector>>,
+    tokio_rustls_client
+);
+
+http_transport!(
+    /// the tokio transport with the native-tls, it is enabled by the `tokio-native-tls` feature
+    "tokio-native-tls",
+    TokioNativeTlsTransport,
+    crate::tokio_native_tls_compat::Connector,
+    tokio_native_tls_client
+);
+
+http_transport!(
+    /// the async-std transport with the rustls, it is enabled by the `async-std-rustls-tls`
+    /// feature
+    "async-std-rustls-tls",
+    AsyncStdRustlsTransport,
+    crate::async_std_compat::RustlsConnector,
+    async_std_rustls_client
+);
+
+http_transport!(
+    /// the async-std transport with the native-tls, it is enabled by the `async-std-native-tls`
+    /// feature
+    "async-std-native-tls",
+    AsyncStdNativeTlsTransport,
+    crate::async_std_compat::NativeTlsConnector,
+    async_std_native_tls_client
+);
+
+/// the transport used when the [`ClientBuilder::transport`](crate::ClientBuilder::transport) is
+/// not set, it is the first enabled one of the `tokio-rustls-tls`, `tokio-native-tls`,
+/// `async-std-rustls-tls` and `async-std-native-tls` features
+#[cfg(feature = "tokio-rustls-tls")]
+pub type DefaultTransport = TokioRustlsTransport;
+
+/// the transport used when the [`ClientBuilder::transport`](crate::ClientBuilder::transport) is
+/// not set, it is the first enabled one of the `tokio-rustls-tls`, `tokio-native-tls`,
+/// `async-std-rustls-tls` and `async-std-native-tls` features
+#[cfg(all(feature = "tokio-native-tls", not(feature = "tokio-rustls-tls")))]
+pub type DefaultTransport = TokioNativeTlsTransport;
+
+/// the transport used when the [`ClientBuilder::transport`](crate::ClientBuilder::transport) is
+/// not set, it is the first enabled one of the `tokio-rustls-tls`, `tokio-native-tls`,
+/// `async-std-rustls-tls` and `async-std-native-tls` features
 #[cfg(all(
-    feature = "tokio-rustls-tls",
-    not(feature = "async-std-rustls-tls"),
-    not(feature = "async-std-native-tls")
+    feature = "async-std-rustls-tls",
+    not(any(feature = "tokio-rustls-tls", feature = "tokio-native-tls"))
 ))]
-pub type HttpClient = hyper::Client<HttpsConnector<HttpConnector>, Body>;
+pub type DefaultTransport = AsyncStdRustlsTransport;
 
+/// the transport used when the [`ClientBuilder::transport`](crate::ClientBuilder::transport) is
+/// not set, it is the first enabled one of the `tokio-rustls-tls`, `tokio-native-tls`,
+/// `async-std-rustls-tls` and `async-std-native-tls` features
 #[cfg(all(
-    any(feature = "async-std-rustls-tls", feature = "async-std-native-tls"),
-    not(feature = "tokio-rustls-tls"),
-    not(feature = "tokio-native-tls")
+    feature = "async-std-native-tls",
+    not(any(
+        feature = "tokio-rustls-tls",
+        feature = "tokio-native-tls",
+        feature = "async-std-rustls-tls"
+    ))
 ))]
-pub type HttpClient = hyper::Client<Connector, Body>;
+pub type DefaultTransport = AsyncStdNativeTlsTransport;
 
-#[cfg(all(
-    any(feature = "async-std-rustls-tls", feature = "async-std-native-tls"),
-    not(feature = "tokio-rustls-tls"),
-    not(feature = "tokio-native-tls")
+/// create the [`DefaultTransport`] with the `config`
+#[cfg(any(
+    feature = "tokio-rustls-tls",
+    feature = "tokio-native-tls",
+    feature = "async-std-rustls-tls",
+    feature = "async-std-native-tls"
 ))]
-pub fn new_http_client() -> HttpClient {
-    hyper::Client::builder()
-        .executor(HyperExecutor)
-        .build(Connector::default())
+pub fn new_default_transport(config: &TransportConfig) -> Result<Arc<dyn Transport>, Error> {
+    Ok(Arc::new(DefaultTransport::new(config)?))
 }
 
-#[cfg(all(
+/// no tls feature is enabled, so there is no default transport
+#[cfg(not(any(
     feature = "tokio-rustls-tls",
-    not(feature = "async-std-rustls-tls"),
-    not(feature = "async-std-native-tls")
-))]
-pub fn new_http_client() -> HttpClient {
+    feature = "tokio-native-tls",
+    feature = "async-std-rustls-tls",
+    feature = "async-std-native-tls"
+)))]
+pub fn new_default_transport(_config: &TransportConfig) -> Result<Arc<dyn Transport>, Error> {
+    Err(Error::Other(
+        "no default transport, enable a tls feature or set the transport".into(),
+    ))
+}
+
+#[cfg(feature = "tokio-rustls-tls")]
+fn tokio_rustls_client(
+    config: &TransportConfig,
+) -> Result<Client<TimeoutConnector<HttpsConnector<ProxyConnector>>, RequestBody>, Error> {
     use hyper_rustls::HttpsConnectorBuilder;
+    use hyper_util::client::legacy::connect::HttpConnector;
+
+    use crate::client::HttpVersion;
 
-    hyper::Client::builder().build(
-        HttpsConnectorBuilder::new()
-            .with_native_roots()
-            .https_or_http()
+    let mut http_connector = HttpConnector::new();
+    http_connector.enforce_http(false);
+
+    let proxy_connector = ProxyConnector {
+        http_connector,
+        proxy: config.proxy.clone(),
+    };
+
+    let builder = HttpsConnectorBuilder::new()
+        .with_tls_config(config.tls.rustls_client_config()?)
+        .https_or_http();
+    let connector = match config.http_version {
+        HttpVersion::Auto => builder
             .enable_http1()
             .enable_http2()
-            .build(),
-    )
-}
+            .wrap_connector(proxy_connector),
+        HttpVersion::Http1Only => builder.enable_http1().wrap_connector(proxy_connector),
+        HttpVersion::Http2Only => builder.enable_http2().wrap_connector(proxy_connector),
+    };
 
-#[cfg(all(
-    feature = "tokio-native-tls",
-    not(feature = "async-std-rustls-tls"),
-    not(feature = "async-std-native-tls")
-))]
-pub type HttpClient = hyper::Client<crate::tokio_native_tls_compat::Connector, Body>;
+    Ok(Client::builder(TokioExecutor::new())
+        .pool_timer(TokioTimer::new())
+        .http2_only(config.http2_only())
+        .build(TimeoutConnector {
+            connector,
+            connect_timeout: config.connect_timeout,
+        }))
+}
 
-#[cfg(all(
-    feature = "tokio-native-tls",
-    not(feature = "async-std-rustls-tls"),
-    not(feature = "async-std-native-tls")
-))]
-pub fn new_http_client() -> HttpClient {
+#[cfg(feature = "tokio-native-tls")]
+fn tokio_native_tls_client(
+    config: &TransportConfig,
+) -> Result<Client<crate::tokio_native_tls_compat::Connector, RequestBody>, Error> {
     use crate::tokio_native_tls_compat::Connector;
 
-    hyper::Client::builder().build(Connector::default())
+    Ok(Client::builder(TokioExecutor::new())
+        .pool_timer(TokioTimer::new())
+        .http2_only(config.http2_only())
+        .build(Connector::new(config)?))
+}
+
+#[cfg(feature = "async-std-rustls-tls")]
+fn async_std_rustls_client(
+    config: &TransportConfig,
+) -> Result<Client<crate::async_std_compat::RustlsConnector, RequestBody>, Error> {
+    use crate::async_std_compat::{HyperExecutor, RustlsConnector};
+
+    Ok(Client::builder(HyperExecutor)
+        .http2_only(config.http2_only())
+        .build(RustlsConnector::new(config)?))
+}
+
+#[cfg(feature = "async-std-native-tls")]
+fn async_std_native_tls_client(
+    config: &TransportConfig,
+) -> Result<Client<crate::async_std_compat::NativeTlsConnector, RequestBody>, Error> {
+    use crate::async_std_compat::{HyperExecutor, NativeTlsConnector};
+
+    Ok(Client::builder(HyperExecutor)
+        .http2_only(config.http2_only())
+        .build(NativeTlsConnector::new(config)?))
+}
+
+#[cfg(feature = "tokio-rustls-tls")]
+pub use self::proxy_connector::{ProxyConnector, TimeoutConnector};
+
+#[cfg(feature = "tokio-rustls-tls")]
+mod proxy_connector {
+    use std::error;
+    use std::io;
+    use std::net::IpAddr;
+    use std::task::{Context, Poll};
+    use std::time::Duration;
+
+    use futures_util::future::BoxFuture;
+    use futures_util::FutureExt;
+    use hyper::Uri;
+    use hyper_util::client::legacy::connect::HttpConnector;
+    use hyper_util::rt::TokioIo;
+    use tokio::net::TcpStream;
+    use tower_service::Service;
+
+    use crate::proxy::{tunnel, Proxy};
+    use crate::timer;
+
+    /// the [`HttpConnector`] which connects through the proxy
+    #[derive(Debug, Clone)]
+    pub struct ProxyConnector {
+        pub http_connector: HttpConnector,
+        pub proxy: Option<Proxy>,
+    }
+
+    impl Service<Uri> for ProxyConnector {
+        type Response = TokioIo<TcpStream>;
+        type Error = Box<dyn error::Error + Send + Sync + 'static>;
+        type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            self.http_connector
+                .poll_ready(cx)
+                .map_err(|err| Box::new(err) as _)
+        }
+
+        fn call(&mut self, req: Uri) -> Self::Future {
+            let host = match req.host() {
+                None => {
+                    let connecting = self.http_connector.call(req);
+
+                    return async move { Ok(connecting.await?) }.boxed();
+                }
+                Some(host) => host.to_string(),
+            };
+            let port = req.port_u16().unwrap_or(match req.scheme_str() {
+                Some("http") => 80,
+                _ => 443,
+            });
+            let http_connector = self.http_connector.clone();
+            let proxy = self.proxy.clone();
+
+            async move {
+                let stream = tunnel::connect(
+                    proxy.as_ref(),
+                    &host,
+                    port,
+                    |host, port| dial(http_connector.clone(), host, port),
+                    resolve,
+                )
+                .await?;
+
+                Ok(TokioIo::new(stream))
+            }
+            .boxed()
+        }
+    }
+
+    /// the connector whose connecting is limited by the connect timeout, the timeout covers the
+    /// tcp connect, the proxy handshake and the tls handshake
+    #[derive(Debug, Clone)]
+    pub struct TimeoutConnector<C> {
+        pub connector: C,
+        pub connect_timeout: Option<Duration>,
+    }
+
+    impl<C> Service<Uri> for TimeoutConnector<C>
+    where
+        C: Service<Uri>,
+        C::Error: Into<Box<dyn error::Error + Send + Sync + 'static>>,
+        C::Future: Send + 'static,
+    {
+        type Response = C::Response;
+        type Error = Box<dyn error::Error + Send + Sync + 'static>;
+        type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            self.connector.poll_ready(cx).map_err(Into::into)
+        }
+
+        fn call(&mut self, req: Uri) -> Self::Future {
+            let connecting = self.connector.call(req);
+            let connect_timeout = self.connect_timeout;
+
+            async move {
+                match timer::maybe_timeout(connect_timeout, connecting).await {
+                    Err(elapsed) => Err(io::Error::from(elapsed).into()),
+                    Ok(result) => result.map_err(Into::into),
+                }
+            }
+            .boxed()
+        }
+    }
+
+    /// connect the `host`:`port` by the [`HttpConnector`]
+    async fn dial(
+        mut http_connector: HttpConnector,
+        host: String,
+        port: u16,
+    ) -> io::Result<TcpStream> {
+        let host = match host.parse::<IpAddr>() {
+            Ok(IpAddr::V6(_)) => format!("[{host}]"),
+            _ => host,
+        };
+        let uri = format!("http://{host}:{port}")
+            .parse::<Uri>()
+            .map_err(io::Error::other)?;
+
+        match http_connector.call(uri).await {
+            Err(err) => Err(io::Error::other(err)),
+            Ok(stream) => Ok(stream.into_inner()),
+        }
+    }
+
+    /// resolve the `host` to the ip locally, for the proxy which does not resolve the host
+    async fn resolve(host: String, port: u16) -> io::Result<IpAddr> {
+        match tokio::net::lookup_host((host.as_str(), port)).await?.next() {
+            None => Err(io::Error::other(format!("{host} is not resolved"))),
+            Some(addr) => Ok(addr.ip()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(any(
+        feature = "tokio-rustls-tls",
+        feature = "tokio-native-tls",
+        feature = "async-std-rustls-tls",
+        feature = "async-std-native-tls"
+    ))]
+    #[test]
+    fn enabled_transports() {
+        use super::*;
+        use crate::tls::TlsConfig;
+
+        let config =
+            TransportConfig::new().with_tls_config(TlsConfig::new().without_native_roots());
+
+        #[cfg(feature = "tokio-rustls-tls")]
+        TokioRustlsTransport::new(&config).unwrap();
+        #[cfg(feature = "tokio-native-tls")]
+        TokioNativeTlsTransport::new(&config).unwrap();
+        #[cfg(feature = "async-std-rustls-tls")]
+        AsyncStdRustlsTransport::new(&config).unwrap();
+        #[cfg(feature = "async-std-native-tls")]
+        AsyncStdNativeTlsTransport::new(&config).unwrap();
+
+        new_default_transport(&config).unwrap();
+    }
+
+    #[cfg(not(any(
+        feature = "tokio-rustls-tls",
+        feature = "tokio-native-tls",
+        feature = "async-std-rustls-tls",
+        feature = "async-std-native-tls"
+    )))]
+    #[test]
+    fn build_without_tls_backend() {
+        use crate::client::{Auth, Client};
+
+        let err = Client::builder()
+            .auth(Auth::new("key".to_string(), "id".to_string()))
+            .no_proxy()
+            .build()
+            .unwrap_err();
+
+        assert!(err.to_string().contains("no default transport"), "{err}");
+    }
+
+    #[cfg(feature = "tokio-rustls-tls")]
+    #[tokio::test]
+    async fn connect_timeout_covers_tls_handshake() {
+        use std::time::{Duration, Instant};
+
+        use tokio::net::TcpListener;
+
+        use crate::client::{Auth, Client};
+        use crate::endpoint::DefaultEndpointResolver;
+        use crate::error::Error;
+        use crate::retry::RetryPolicy;
+        use crate::test_util::{TestApi, TestRequest};
+        use crate::tls::TlsConfig;
+
+        // accept the tcp connection but never answer the tls handshake
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let mut streams = vec![];
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                streams.push(stream);
+            }
+        });
+
+        let client = Client::builder()
+            .auth(Auth::new("key".to_string(), "id".to_string()))
+            .endpoint_resolver(DefaultEndpointResolver::custom(
+                format!("https://{addr}").parse().unwrap(),
+            ))
+            .no_proxy()
+            .tls_config(TlsConfig::new().without_native_roots())
+            .connect_timeout(Duration::from_millis(200))
+            .retry_policy(RetryPolicy::disabled())
+            .build()
+            .unwrap();
+
+        let start = Instant::now();
+        let err = client
+            .send::<TestApi>(&TestRequest::default())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, Error::Connect(_)), "error: {err:?}");
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        server.abort();
+    }
+
+    #[cfg(feature = "tokio-rustls-tls")]
+    #[tokio::test]
+    async fn retry_closed_connection() {
+        use std::time::Duration;
+
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::TcpListener;
+
+        use crate::client::{Auth, Client};
+        use crate::endpoint::DefaultEndpointResolver;
+        use crate::retry::RetryPolicy;
+        use crate::test_util::{TestApi, TestRequest};
+
+        // close the first connection after reading the request, answer the second one
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0; 4096];
+            let _ = stream.read(&mut buf).await.unwrap();
+            drop(stream);
+
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let _ = stream.read(&mut buf).await.unwrap();
+            let body = r#"{"Response":{"Status":"ok","RequestId":"request-id"}}"#;
+            stream
+                .write_all(
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{body}",
+                        body.len()
+                    )
+                    .as_bytes(),
+                )
+                .await
+                .unwrap();
+        });
+
+        let client = Client::builder()
+            .auth(Auth::new("key".to_string(), "id".to_string()))
+            .endpoint_resolver(DefaultEndpointResolver::custom(
+                format!("http://{addr}").parse().unwrap(),
+            ))
+            .no_proxy()
+            .retry_policy(
+                RetryPolicy::default()
+                    .with_max_attempts(2)
+                    .with_backoff(Duration::ZERO, Duration::ZERO),
+            )
+            .build()
+            .unwrap();
+
+        let (response, _) = client
+            .send::<TestApi>(&TestRequest::default())
+            .await
+            .unwrap();
+        assert_eq!(response.status.as_deref(), Some("ok"));
+
+        server.await.unwrap();
+    }
+
+    #[cfg(feature = "tokio-rustls-tls")]
+    #[tokio::test]
+    async fn connect_through_ipv6_proxy() {
+        use std::net::Ipv6Addr;
+
+        use hyper_util::client::legacy::connect::HttpConnector;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::TcpListener;
+        use tower_service::Service;
+
+        use super::ProxyConnector;
+        use crate::proxy::Proxy;
+
+        let listener = TcpListener::bind("[::1]:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            // the http proxy
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut head = Vec::new();
+            while !head.ends_with(b"\r\n\r\n") {
+                head.push(stream.read_u8().await.unwrap());
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+                .await
+                .unwrap();
+
+            // the socks5 proxy, the ipv6 target is resolved locally
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut greeting = [0; 3];
+            stream.read_exact(&mut greeting).await.unwrap();
+            stream.write_all(&[5, 0]).await.unwrap();
+            let mut request = [0; 4 + 16 + 2];
+            stream.read_exact(&mut request).await.unwrap();
+            stream
+                .write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0])
+                .await
+                .unwrap();
+
+            (String::from_utf8(head).unwrap(), request)
+        });
+
+        let mut http_connector = HttpConnector::new();
+        http_connector.enforce_http(false);
+
+        let mut connector = ProxyConnector {
+            http_connector: http_connector.clone(),
+            proxy: Some(Proxy::new(&format!("http://[::1]:{port}")).unwrap()),
+        };
+        connector
+            .call("https://example.com".parse().unwrap())
+            .await
+            .unwrap();
+
+        let mut connector = ProxyConnector {
+            http_connector,
+            proxy: Some(Proxy::new(&format!("socks5://[::1]:{port}")).unwrap()),
+        };
+        connector
+            .call("https://[::1]:8443".parse().unwrap())
+            .await
+            .unwrap();
+
+        let (head, request) = server.await.unwrap();
+        assert!(
+            head.starts_with("CONNECT example.com:443 HTTP/1.1\r\n"),
+            "{head}"
+        );
+
+        let mut expected = vec![5, 1, 0, 4];
+        expected.extend_from_slice(&Ipv6Addr::LOCALHOST.octets());
+        expected.extend_from_slice(&8443_u16.to_be_bytes());
+        assert_eq!(request.as_slice(), expected);
+    }
+
+    #[cfg(feature = "tokio-rustls-tls")]
+    #[tokio::test]
+    async fn builder_http_version() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::TcpListener;
+
+        use crate::client::{Auth, Client, HttpVersion};
+        use crate::endpoint::DefaultEndpointResolver;
+        use crate::retry::RetryPolicy;
+        use crate::test_util::{TestApi, TestRequest};
+
+        /// send a request by the client with the `http_version`, get the first bytes received
+        /// by the server
+        async fn first_bytes(http_version: HttpVersion) -> Vec<u8> {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let server = tokio::spawn(async move {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut received = vec![0; 16];
+                stream.read_exact(&mut received).await.unwrap();
+
+                let body = r#"{"Response":{"RequestId":"request-id"}}"#;
+                let _ = stream
+                    .write_all(
+                        format!(
+                            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{body}",
+                            body.len()
+                        )
+                        .as_bytes(),
+                    )
+                    .await;
+
+                received
+            });
+
+            let client = Client::builder()
+                .auth(Auth::new("key".to_string(), "id".to_string()))
+                .endpoint_resolver(DefaultEndpointResolver::custom(
+                    format!("http://{addr}").parse().unwrap(),
+                ))
+                .no_proxy()
+                .http_version(http_version)
+                .retry_policy(RetryPolicy::disabled())
+                .build()
+                .unwrap();
+            let sent = client.send::<TestApi>(&TestRequest::default()).await;
+            if http_version == HttpVersion::Http1Only {
+                sent.unwrap();
+            }
+
+            server.await.unwrap()
+        }
+
+        assert_eq!(
+            first_bytes(HttpVersion::Http1Only).await,
+            b"POST / HTTP/1.1\r"
+        );
+
+        // the prior knowledge http2 starts with the connection preface
+        assert_eq!(
+            first_bytes(HttpVersion::Http2Only).await,
+            b"PRI * HTTP/2.0\r\n"
+        );
+    }
+
+    #[cfg(feature = "async-std-rustls-tls")]
+    #[test]
+    fn async_std_rustls_connect_ipv6() {
+        use std::time::Duration;
+
+        use async_std::future::timeout;
+        use async_std::io::ReadExt;
+        use async_std::net::TcpListener;
+        use async_std::task;
+        use tower_service::Service;
+
+        use crate::async_std_compat::RustlsConnector;
+        use crate::tls::TlsConfig;
+        use crate::transport::TransportConfig;
+
+        task::block_on(async {
+            let listener = TcpListener::bind("[::1]:0").await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+
+            let config =
+                TransportConfig::new().with_tls_config(TlsConfig::new().without_native_roots());
+            let mut connector = RustlsConnector::new(&config).unwrap();
+            let connecting = connector.call(format!("https://[::1]:{port}").parse().unwrap());
+            let client = task::spawn(async move {
+                let _ = connecting.await;
+            });
+
+            // the tls handshake starts with the client hello record
+            let (mut stream, _) = timeout(Duration::from_secs(5), listener.accept())
+                .await
+                .expect("the connector doesn't connect the ipv6 host")
+                .unwrap();
+            let mut record_type = [0; 1];
+            stream.read_exact(&mut record_type).await.unwrap();
+            assert_eq!(record_type, [0x16]);
+
+            drop(stream);
+            client.await;
+        });
+    }
 }
@@ -0,0 +1,209 @@
+//! idempotency token of the create apis
+//!
+//! the create apis such as the cvm `RunInstances` accept the `ClientToken`, the requests with the
+//! same token create the resource only once
+//!
+//! the [`Api::IDEMPOTENCY_TOKEN_FIELD`] is the opt-in, when it is set, all the `send` methods of
+//! the [`Client`] generate the token once per call if the request doesn't set it or sets it to
+//! the empty string, and reuse it across the retries; the `#[tc(idempotent)]` of the derive and
+//! the generated apis set it to `ClientToken`
+//!
+//! [`Api::IDEMPOTENCY_TOKEN_FIELD`]: crate::api::Api::IDEMPOTENCY_TOKEN_FIELD
+//! [`Client`]: crate::Client
+
+use serde::Serialize;
+use serde_json::Value;
+use tracing::trace;
+
+use crate::error::Error;
+
+/// marshal the `request` of the `action`, generate the token into the `token_field` if it is
+/// set but the request doesn't set the token, the empty token is treated as unset
+pub(crate) fn marshal(
+    request: &impl Serialize,
+    token_field: Option<&str>,
+    action: &str,
+) -> Result<Vec<u8>, Error> {
+    let Some(token_field) = token_field else {
+        let payload = serde_json::to_vec(request)?;
+
+        trace!("marshal request done");
+
+        return Ok(payload);
+    };
+
+    let mut request = serde_json::to_value(request)?;
+    match &mut request {
+        Value::Object(request) => {
+            let token = request.entry(token_field).or_insert(Value::Null);
+            if token.is_null() || token.as_str() == Some("") {
+                *token = Value::String(uuid_v4());
+            }
+
+            trace!(%token, "set idempotency token done");
+        }
+
+        _ => {
+            return Err(Error::Other(
+                format!("the {action} request is not an object").into(),
+            ))
+        }
+    }
+
+    let payload = serde_json::to_vec(&request)?;
+
+    trace!("marshal request done");
+
+    Ok(payload)
+}
+
+/// generate the random uuid v4
+fn uuid_v4() -> String {
+    // the version is 4 and the variant is RFC 4122
+    let uuid = fastrand::u128(..) & !(0xf_u128 << 76) | (0x4_u128 << 76);
+    let uuid = uuid & !(0xc_u128 << 60) | (0x8_u128 << 60);
+    let hex = format!("{uuid:032x}");
+
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::api::Api;
+    use crate::client::RequestOptions;
+    use crate::retry::RetryPolicy;
+    use crate::test_util::{self, MockTransport, TestApi, TestRequest, TestResponse};
+
+    /// the [`TestApi`] which sets the idempotency token field
+    #[derive(Debug)]
+    struct CreateApi;
+
+    impl Api for CreateApi {
+        type Request = TestRequest;
+        type Response = TestResponse;
+        const VERSION: &'static str = "2017-03-12";
+        const ACTION: &'static str = "Create";
+        const SERVICE: &'static str = "cvm";
+        const HOST: &'static str = "cvm.tencentcloudapi.com";
+        const IDEMPOTENCY_TOKEN_FIELD: Option<&'static str> = Some("ClientToken");
+    }
+
+    fn tokens(transport: &MockTransport) -> Vec<Value> {
+        transport
+            .requests()
+            .into_iter()
+            .map(|request| request.body["ClientToken"].clone())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn send_reuses_token_across_retries() {
+        let transport = MockTransport::new();
+        transport
+            .push_api_error("InternalError")
+            .push_ok(json!({}))
+            .push_ok(json!({}));
+        let client = test_util::builder(transport.clone())
+            .retry_policy(RetryPolicy::default().with_backoff(Duration::ZERO, Duration::ZERO))
+            .build()
+            .unwrap();
+
+        client
+            .send::<CreateApi>(&TestRequest::default())
+            .await
+            .unwrap();
+        client
+            .send::<CreateApi>(&TestRequest::default())
+            .await
+            .unwrap();
+
+        let tokens = tokens(&transport);
+        assert!(tokens[0].is_string());
+        assert_eq!(tokens[0], tokens[1]);
+        assert_ne!(tokens[1], tokens[2]);
+    }
+
+    #[tokio::test]
+    async fn send_keeps_request_token() {
+        let transport = MockTransport::new();
+        transport.push_ok(json!({}));
+        let client = test_util::builder(transport.clone()).build().unwrap();
+
+        let request = TestRequest {
+            client_token: Some("token".to_string()),
+            ..Default::default()
+        };
+        client
+            .send_with::<CreateApi>(&request, &RequestOptions::new())
+            .await
+            .unwrap();
+
+        assert_eq!(tokens(&transport), [json!("token")]);
+    }
+
+    #[tokio::test]
+    async fn send_without_token_field() {
+        let transport = MockTransport::new();
+        transport.push_ok(json!({}));
+        let client = test_util::builder(transport.clone()).build().unwrap();
+
+        client
+            .send::<TestApi>(&TestRequest::default())
+            .await
+            .unwrap();
+
+        assert_eq!(transport.requests()[0].body, json!({}));
+    }
+
+    #[tokio::test]
+    async fn send_replaces_empty_token() {
+        let transport = MockTransport::new();
+        transport.push_ok(json!({})).push_ok(json!({}));
+        let client = test_util::builder(transport.clone()).build().unwrap();
+
+        let request = TestRequest {
+            client_token: Some(String::new()),
+            ..Default::default()
+        };
+        client.send::<CreateApi>(&request).await.unwrap();
+        client
+            .send_with::<CreateApi>(&request, &RequestOptions::new().with_region("ap-shanghai"))
+            .await
+            .unwrap();
+
+        let tokens = tokens(&transport);
+        assert!(tokens
+            .iter()
+            .all(|token| token.as_str().is_some_and(|token| !token.is_empty())));
+        assert_ne!(tokens[0], tokens[1]);
+    }
+
+    #[test]
+    fn marshal_non_object_request() {
+        assert!(marshal(&json!([]), Some("ClientToken"), "Create").is_err());
+        assert_eq!(marshal(&json!([]), None, "Create").unwrap(), b"[]");
+    }
+
+    #[test]
+    fn uuid_v4_format() {
+        let uuid = uuid_v4();
+
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(uuid.matches('-').count(), 4);
+        assert_eq!(&uuid[14..15], "4");
+        assert!(matches!(&uuid[19..20], "8" | "9" | "a" | "b"), "{uuid}");
+        assert_ne!(uuid, uuid_v4());
+    }
+}
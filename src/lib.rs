@@ -2,15 +2,41 @@
 //!
 //! this crate provides a generic [`Client`] and [`api::Api`]
 
-pub use self::client::{Auth, Client};
+pub use self::client::{Auth, Client, ClientBuilder, RequestOptions, ResponseMeta};
+pub use self::credential::CredentialProvider;
+pub use self::endpoint::{Endpoint, EndpointResolver};
 pub use self::error::Error;
+#[cfg(feature = "derive")]
+pub use tencentcloud_derive::TencentApi;
 
 pub mod api;
 #[cfg(any(feature = "async-std-native-tls", feature = "async-std-rustls-tls"))]
 mod async_std_compat;
 pub mod client;
+mod clock;
+pub mod credential;
+pub mod endpoint;
 pub mod error;
 mod http_client;
+mod idempotent;
+pub mod paginate;
+pub mod proxy;
+pub mod retry;
+pub mod service;
+pub mod services;
 mod tc3_hmac;
+#[cfg(test)]
+mod test_util;
+mod timer;
+pub mod tls;
 #[cfg(feature = "tokio-native-tls")]
 mod tokio_native_tls_compat;
+#[cfg(feature = "tower")]
+pub mod tower;
+pub mod transport;
+pub mod waiter;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use serde;
+}
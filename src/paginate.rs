@@ -0,0 +1,416 @@
+//! pagination of the list apis
+//!
+//! the [`Paginated`] api pages through the `Offset`/`Limit` with the `TotalCount`, or the
+//! `NextToken`/`Context` cursor, the [`Client::paginate`] sends the requests page by page and
+//! returns the stream of the items
+//!
+//! ## Examples:
+//!
+//! ```rust,no_run
+//! use futures_util::TryStreamExt;
+//! use serde::{Deserialize, Serialize};
+//! use tencentcloud::api::Api;
+//! use tencentcloud::paginate::{Cursor, Page, Paginated};
+//! use tencentcloud::{Auth, Client};
+//!
+//! #[derive(Debug, Copy, Clone)]
+//! pub struct DescribeInstances;
+//!
+//! #[derive(Debug, Clone, Default, Serialize)]
+//! pub struct DescribeInstancesRequest {
+//!     #[serde(rename = "Offset")]
+//!     pub offset: u64,
+//!     #[serde(rename = "Limit")]
+//!     pub limit: u64,
+//! }
+//!
+//! #[derive(Debug, Clone, Deserialize)]
+//! pub struct DescribeInstancesResponse {
+//!     #[serde(rename = "TotalCount")]
+//!     pub total_count: u64,
+//!     #[serde(rename = "InstanceSet")]
+//!     pub instance_set: Vec<serde_json::Value>,
+//! }
+//!
+//! impl Api for DescribeInstances {
+//!     type Request = DescribeInstancesRequest;
+//!     type Response = DescribeInstancesResponse;
+//!     const VERSION: &'static str = "2017-03-12";
+//!     const ACTION: &'static str = "DescribeInstances";
+//!     const SERVICE: &'static str = "cvm";
+//!     const HOST: &'static str = "cvm.tencentcloudapi.com";
+//! }
+//!
+//! impl Paginated for DescribeInstances {
+//!     type Item = serde_json::Value;
+//!
+//!     fn set_page(request: &mut Self::Request, cursor: &Cursor, page_size: u64) {
+//!         request.offset = cursor.offset;
+//!         request.limit = page_size;
+//!     }
+//!
+//!     fn into_page(response: Self::Response) -> Page<Self::Item> {
+//!         Page::new(response.instance_set).with_total_count(response.total_count)
+//!     }
+//! }
+//!
+//! # async fn list() -> Result<(), tencentcloud::Error> {
+//! let client = Client::new("ap-guangzhou".to_string(), Auth::new("key".into(), "id".into()), None);
+//! let instances = client
+//!     .paginate::<DescribeInstances>(Default::default(), 100)
+//!     .try_collect::<Vec<_>>()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+
+use futures_util::{stream, Stream};
+use tracing::trace;
+
+use crate::api::Api;
+use crate::client::Client;
+use crate::error::Error;
+
+/// how the api pages
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum PageStyle {
+    /// page by the `Offset`/`Limit`, the paging stops when the `TotalCount` is reached, or the
+    /// page is not full if the api doesn't return the total count
+    #[default]
+    Offset,
+
+    /// page by the cursor such as the `NextToken` or `Context`, the paging stops when the api
+    /// doesn't return the next token
+    Token,
+}
+
+/// the api which pages through the items
+pub trait Paginated: Api
+where
+    Self::Request: Clone,
+{
+    /// the item type
+    type Item;
+
+    /// the paging style, default is [`PageStyle::Offset`]
+    const STYLE: PageStyle = PageStyle::Offset;
+
+    /// set the page `cursor` and the `page_size` into the `request`
+    fn set_page(request: &mut Self::Request, cursor: &Cursor, page_size: u64);
+
+    /// take the items and the paging info from the `response`
+    fn into_page(response: Self::Response) -> Page<Self::Item>;
+}
+
+/// the position of the next page
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct Cursor {
+    /// the number of the items before the page, for the [`PageStyle::Offset`]
+    pub offset: u64,
+
+    /// the next token returned by the previous page, `None` for the first page, for the
+    /// [`PageStyle::Token`]
+    pub token: Option<String>,
+}
+
+/// a page of the items
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Page<T> {
+    /// the items of the page
+    pub items: Vec<T>,
+
+    /// the total count of the items, for the [`PageStyle::Offset`]
+    pub total_count: Option<u64>,
+
+    /// the token of the next page, for the [`PageStyle::Token`]
+    pub next_token: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// create the page with the `items`
+    pub fn new(items: Vec<T>) -> Self {
+        Self {
+            items,
+            total_count: None,
+            next_token: None,
+        }
+    }
+
+    /// set the total count of the items
+    pub fn with_total_count(mut self, total_count: u64) -> Self {
+        self.total_count = Some(total_count);
+
+        self
+    }
+
+    /// set the token of the next page, the empty token means no more page
+    pub fn with_next_token(mut self, next_token: impl Into<Option<String>>) -> Self {
+        self.next_token = next_token.into().filter(|token| !token.is_empty());
+
+        self
+    }
+}
+
+struct PageState<A: Paginated>
+where
+    A::Request: Clone,
+{
+    request: A::Request,
+    page_size: u64,
+    cursor: Cursor,
+    items: VecDeque<A::Item>,
+    done: bool,
+}
+
+impl Client {
+    /// send the `A`: [`Paginated`] api page by page, get the stream of the items
+    ///
+    /// every page is requested with the `page_size`, the next page is requested when the items
+    /// of the current page are consumed; the stream ends after an error
+    ///
+    /// the `page_size` must be positive, otherwise the stream only returns an error
+    pub fn paginate<'a, A>(
+        &'a self,
+        request: A::Request,
+        page_size: u64,
+    ) -> impl Stream<Item = Result<A::Item, Error>> + 'a
+    where
+        A: Paginated + 'a,
+        A::Request: Clone,
+    {
+        let state = PageState::<A> {
+            request,
+            page_size,
+            cursor: Cursor::default(),
+            items: VecDeque::new(),
+            done: false,
+        };
+
+        stream::unfold(state, move |mut state| async move {
+            loop {
+                if let Some(item) = state.items.pop_front() {
+                    return Some((Ok(item), state));
+                }
+                if state.done {
+                    return None;
+                }
+
+                if let Err(err) = self.next_page(&mut state).await {
+                    state.done = true;
+
+                    return Some((Err(err), state));
+                }
+            }
+        })
+    }
+
+    async fn next_page<A>(&self, state: &mut PageState<A>) -> Result<(), Error>
+    where
+        A: Paginated,
+        A::Request: Clone,
+    {
+        if state.page_size == 0 {
+            return Err(Error::Other("page size must be positive".into()));
+        }
+
+        let mut request = state.request.clone();
+        A::set_page(&mut request, &state.cursor, state.page_size);
+
+        let (response, request_id) = self.send::<A>(&request).await?;
+        let page = A::into_page(response);
+        let count = page.items.len() as u64;
+
+        trace!(%request_id, count, cursor = ?state.cursor, "get page done");
+
+        state.items.extend(page.items);
+        state.cursor.offset += count;
+
+        state.done = match A::STYLE {
+            PageStyle::Offset => match page.total_count {
+                Some(total_count) => count == 0 || state.cursor.offset >= total_count,
+                None => count < state.page_size,
+            },
+
+            // the same token means the api doesn't move the cursor, stop to avoid the endless loop
+            PageStyle::Token => match page.next_token {
+                None => true,
+                Some(token) if state.cursor.token.as_ref() == Some(&token) => true,
+                Some(token) => {
+                    state.cursor.token = Some(token);
+
+                    false
+                }
+            },
+        };
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::{StreamExt, TryStreamExt};
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_util::{self, MockTransport};
+
+    #[derive(Debug, Clone, Default, Serialize)]
+    struct ListRequest {
+        #[serde(rename = "Offset")]
+        offset: u64,
+        #[serde(rename = "Limit")]
+        limit: u64,
+        #[serde(rename = "NextToken", skip_serializing_if = "Option::is_none")]
+        next_token: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct ListResponse {
+        #[serde(rename = "TotalCount")]
+        total_count: Option<u64>,
+        #[serde(rename = "NextToken")]
+        next_token: Option<String>,
+        #[serde(rename = "Items")]
+        items: Vec<u64>,
+    }
+
+    macro_rules! list_api {
+        ($name:ident, $style:expr) => {
+            struct $name;
+
+            impl Api for $name {
+                type Request = ListRequest;
+                type Response = ListResponse;
+                const VERSION: &'static str = "2017-03-12";
+                const ACTION: &'static str = "List";
+                const SERVICE: &'static str = "cvm";
+                const HOST: &'static str = "cvm.tencentcloudapi.com";
+            }
+
+            impl Paginated for $name {
+                type Item = u64;
+                const STYLE: PageStyle = $style;
+
+                fn set_page(request: &mut Self::Request, cursor: &Cursor, page_size: u64) {
+                    request.offset = cursor.offset;
+                    request.limit = page_size;
+                    request.next_token = cursor.token.clone();
+                }
+
+                fn into_page(response: Self::Response) -> Page<Self::Item> {
+                    let page = Page::new(response.items).with_next_token(response.next_token);
+
+                    match response.total_count {
+                        None => page,
+                        Some(total_count) => page.with_total_count(total_count),
+                    }
+                }
+            }
+        };
+    }
+
+    list_api!(OffsetList, PageStyle::Offset);
+    list_api!(TokenList, PageStyle::Token);
+
+    #[tokio::test]
+    async fn offset_with_total_count() {
+        let transport = MockTransport::new();
+        transport
+            .push_ok(json!({ "TotalCount": 3, "Items": [1, 2] }))
+            .push_ok(json!({ "TotalCount": 3, "Items": [3] }));
+        let client = test_util::builder(transport.clone()).build().unwrap();
+
+        let items = client
+            .paginate::<OffsetList>(Default::default(), 2)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        assert_eq!(items, [1, 2, 3]);
+        let offsets = transport
+            .requests()
+            .iter()
+            .map(|request| request.body["Offset"].as_u64().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(offsets, [0, 2]);
+    }
+
+    #[tokio::test]
+    async fn offset_without_total_count_stops_on_short_page() {
+        let transport = MockTransport::new();
+        transport
+            .push_ok(json!({ "Items": [1, 2] }))
+            .push_ok(json!({ "Items": [] }));
+        let client = test_util::builder(transport.clone()).build().unwrap();
+
+        let items = client
+            .paginate::<OffsetList>(Default::default(), 2)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        assert_eq!(items, [1, 2]);
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn token_stops_on_missing_or_repeated_token() {
+        let transport = MockTransport::new();
+        transport
+            .push_ok(json!({ "NextToken": "a", "Items": [1] }))
+            .push_ok(json!({ "NextToken": "b", "Items": [2] }))
+            .push_ok(json!({ "NextToken": "b", "Items": [3] }));
+        let client = test_util::builder(transport.clone()).build().unwrap();
+
+        let items = client
+            .paginate::<TokenList>(Default::default(), 1)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        assert_eq!(items, [1, 2, 3]);
+        let tokens = transport
+            .requests()
+            .iter()
+            .map(|request| request.body.get("NextToken").cloned())
+            .collect::<Vec<_>>();
+        assert_eq!(tokens, [None, Some(json!("a")), Some(json!("b"))]);
+    }
+
+    #[tokio::test]
+    async fn zero_page_size_is_rejected() {
+        let transport = MockTransport::new();
+        let client = test_util::builder(transport.clone()).build().unwrap();
+
+        let result = client
+            .paginate::<OffsetList>(Default::default(), 0)
+            .try_collect::<Vec<_>>()
+            .await;
+
+        assert!(matches!(result, Err(Error::Other(_))));
+        assert!(transport.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn stream_ends_after_error() {
+        let transport = MockTransport::new();
+        transport
+            .push_ok(json!({ "TotalCount": 4, "Items": [1, 2] }))
+            .push_api_error("ResourceNotFound");
+        let client = test_util::builder(transport).build().unwrap();
+
+        let results = client
+            .paginate::<OffsetList>(Default::default(), 2)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(results.len(), 3);
+        assert!(results[2].is_err());
+    }
+}
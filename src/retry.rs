@@ -0,0 +1,315 @@
+//! retry policy
+//!
+//! the [`Client`](crate::Client) retries the connection failures and the transient tencentcloud
+//! api errors with jittered exponential backoff, every retry rebuilds and re-signs the request
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::{Error, ErrorCode};
+
+/// retry policy
+///
+/// the default policy allows 3 attempts, the backoff starts at 100ms and is capped at 5s, with a
+/// [`RetryBudget`] shared by the clones of the policy
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    budget: Option<Arc<RetryBudget>>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(5),
+            budget: Some(Arc::new(RetryBudget::default())),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// the policy which never retries
+    pub fn disabled() -> Self {
+        Self {
+            max_attempts: 1,
+            budget: None,
+            ..Default::default()
+        }
+    }
+
+    /// set the max attempts, includes the first attempt, `0` is treated as `1`
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+
+        self
+    }
+
+    /// set the backoff base delay and max delay
+    ///
+    /// the delay before the `n`th retry (starts from `0`) is a random duration between `0` and
+    /// `min(max_delay, base_delay * 2^n)`
+    pub fn with_backoff(mut self, base_delay: Duration, max_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self.max_delay = max_delay;
+
+        self
+    }
+
+    /// set the [`RetryBudget`], `None` means no budget limit
+    pub fn with_budget(mut self, budget: impl Into<Option<RetryBudget>>) -> Self {
+        self.budget = budget.into().map(Arc::new);
+
+        self
+    }
+
+    /// the max attempts, includes the first attempt
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// decide whether the failed `attempt` should be retried, returns the backoff delay
+    ///
+    /// `attempt` starts from `1`
+    pub(crate) fn should_retry(&self, attempt: u32, err: &Error) -> Option<Duration> {
+        if attempt >= self.max_attempts || !is_retryable(err) {
+            return None;
+        }
+
+        if let Some(budget) = &self.budget {
+            if !budget.withdraw() {
+                return None;
+            }
+        }
+
+        Some(self.backoff(attempt))
+    }
+
+    /// record a successful call, refill the budget
+    pub(crate) fn on_success(&self) {
+        if let Some(budget) = &self.budget {
+            budget.deposit();
+        }
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exp = self
+            .base_delay
+            .saturating_mul(1 << (attempt - 1).min(16))
+            .min(self.max_delay);
+
+        // full jitter
+        exp.mul_f64(fastrand::f64())
+    }
+}
+
+/// retry budget
+///
+/// the budget is a token bucket, every retry withdraws `retry_cost` tokens and every successful
+/// call deposits `success_refill` tokens, when the bucket is empty the errors are returned
+/// without retrying, so a broken service won't be flooded with retries
+#[derive(Debug)]
+pub struct RetryBudget {
+    tokens: AtomicU32,
+    capacity: u32,
+    retry_cost: u32,
+    success_refill: u32,
+}
+
+impl Default for RetryBudget {
+    fn default() -> Self {
+        Self::new(500, 5, 1)
+    }
+}
+
+impl RetryBudget {
+    /// create a full retry budget
+    pub fn new(capacity: u32, retry_cost: u32, success_refill: u32) -> Self {
+        Self {
+            tokens: AtomicU32::new(capacity),
+            capacity,
+            retry_cost,
+            success_refill,
+        }
+    }
+
+    fn withdraw(&self) -> bool {
+        self.tokens
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |tokens| {
+                tokens.checked_sub(self.retry_cost)
+            })
+            .is_ok()
+    }
+
+    fn deposit(&self) {
+        let _ = self
+            .tokens
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |tokens| {
+                Some(
+                    tokens
+                        .saturating_add(self.success_refill)
+                        .min(self.capacity),
+                )
+            });
+    }
+}
+
+/// whether the `err` is transient
+fn is_retryable(err: &Error) -> bool {
+    match err {
+        Error::Connect(_) => true,
+        Error::Api { .. } | Error::HttpStatus(_) => err.code().is_some_and(ErrorCode::is_retryable),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::error::ApiError;
+    use crate::test_util::{self, MockTransport, TestApi, TestRequest};
+
+    fn api_error(code: &str) -> Error {
+        Error::Api {
+            err: serde_json::from_value::<ApiError>(json!({ "Code": code, "Message": "m" }))
+                .unwrap(),
+            request_id: "r".to_string(),
+        }
+    }
+
+    fn connect_error() -> Error {
+        Error::Connect(io::Error::from(io::ErrorKind::ConnectionRefused).into())
+    }
+
+    #[test]
+    fn retryable_errors() {
+        let policy = RetryPolicy::default().with_budget(None);
+        let cases = [
+            (connect_error(), true),
+            (api_error("InternalError"), true),
+            (api_error("RequestLimitExceeded.UinLimitExceeded"), true),
+            (api_error("AuthFailure.SignatureExpire"), true),
+            (api_error("AuthFailure.SignatureFailure"), false),
+            (api_error("ResourceNotFound"), false),
+            (api_error("InvalidParameter"), false),
+            (Error::Timeout, false),
+            (Error::Other("other".into()), false),
+        ];
+
+        for (err, retryable) in cases {
+            assert_eq!(
+                policy.should_retry(1, &err).is_some(),
+                retryable,
+                "error: {err}"
+            );
+        }
+    }
+
+    #[test]
+    fn max_attempts() {
+        let policy = RetryPolicy::default()
+            .with_max_attempts(3)
+            .with_budget(None);
+        let err = api_error("InternalError");
+
+        assert!(policy.should_retry(1, &err).is_some());
+        assert!(policy.should_retry(2, &err).is_some());
+        assert!(policy.should_retry(3, &err).is_none());
+
+        assert_eq!(
+            RetryPolicy::default().with_max_attempts(0).max_attempts(),
+            1
+        );
+        assert!(RetryPolicy::disabled().should_retry(1, &err).is_none());
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let policy = RetryPolicy::default()
+            .with_max_attempts(100)
+            .with_backoff(Duration::from_millis(100), Duration::from_millis(300))
+            .with_budget(None);
+        let err = connect_error();
+
+        for attempt in 1..50 {
+            let delay = policy.should_retry(attempt, &err).unwrap();
+            let cap =
+                Duration::from_millis(100 << (attempt - 1).min(16)).min(Duration::from_millis(300));
+
+            assert!(delay <= cap, "attempt: {attempt}, delay: {delay:?}");
+        }
+    }
+
+    #[test]
+    fn budget_is_shared_and_refilled() {
+        let policy = RetryPolicy::default()
+            .with_max_attempts(10)
+            .with_budget(RetryBudget::new(10, 5, 2));
+        let cloned = policy.clone();
+        let err = connect_error();
+
+        assert!(policy.should_retry(1, &err).is_some());
+        assert!(cloned.should_retry(1, &err).is_some());
+        // the budget is empty
+        assert!(policy.should_retry(1, &err).is_none());
+        assert!(cloned.should_retry(1, &err).is_none());
+
+        // 3 successes refill 6 tokens, enough for 1 retry
+        for _ in 0..3 {
+            cloned.on_success();
+        }
+        assert!(policy.should_retry(1, &err).is_some());
+        assert!(policy.should_retry(1, &err).is_none());
+
+        // the refill never exceeds the capacity
+        for _ in 0..100 {
+            policy.on_success();
+        }
+        assert!(policy.should_retry(1, &err).is_some());
+        assert!(policy.should_retry(1, &err).is_some());
+        assert!(policy.should_retry(1, &err).is_none());
+    }
+
+    #[tokio::test]
+    async fn client_retries_transient_error() {
+        let transport = MockTransport::new();
+        transport
+            .push_api_error("InternalError")
+            .push_ok(json!({ "Status": "OK" }));
+        let client = test_util::builder(transport.clone())
+            .retry_policy(RetryPolicy::default().with_backoff(Duration::ZERO, Duration::ZERO))
+            .build()
+            .unwrap();
+
+        let (resp, _) = client
+            .send::<TestApi>(&TestRequest::default())
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status.as_deref(), Some("OK"));
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn client_does_not_retry_permanent_error() {
+        let transport = MockTransport::new();
+        transport.push_api_error("InvalidParameter");
+        let client = test_util::builder(transport.clone()).build().unwrap();
+
+        let err = client
+            .send::<TestApi>(&TestRequest::default())
+            .await
+            .unwrap_err();
+
+        assert!(err.code().is_some_and(ErrorCode::is_invalid_parameter));
+        assert_eq!(transport.requests().len(), 1);
+    }
+}
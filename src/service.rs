@@ -0,0 +1,1522 @@
+//! the api call pipeline
+//!
+//! the [`Client`] sends the [`ApiRequest`] by a pipeline of the tower services, every step of the
+//! pipeline is a tower [`Layer`], from the outermost one:
+//!
+//! - [`TraceLayer`]: the tracing span of the api call
+//! - [`RetryLayer`]: retry the failed call by the [`RetryPolicy`], every attempt re-signs the
+//!   request, so the timestamp and signature are fresh
+//! - [`RateLimitLayer`]: limit the attempts sent, see [`ClientBuilder::rate_limit`]
+//! - [`TimeoutLayer`]: the timeout of every attempt
+//! - [`SigningLayer`]: sign the [`ApiRequest`] into the [`SignedRequest`], send it by the inner
+//!   service and read the [`ApiResponse`]
+//! - [`TransportService`]: send the [`SignedRequest`] by the [`Transport`]
+//!
+//! the [`Client`] is the tower service of the [`ApiRequest`] by the whole pipeline, it applies the
+//! total timeout; it is also the tower service of the [`SignedRequest`] by its transport, so the
+//! application can sign the request by [`Client::sign`], run its middlewares, then send it; the
+//! layers can also be composed with the application layers into another pipeline
+//!
+//! ## Examples:
+//!
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use tencentcloud::retry::RetryPolicy;
+//! use tencentcloud::service::{
+//!     RetryLayer, SigningLayer, TimeoutLayer, TraceLayer, TransportService,
+//! };
+//! use tencentcloud::transport::Transport;
+//! use tencentcloud::Auth;
+//! use tower_layer::Layer;
+//!
+//! fn pipeline(transport: impl Transport + 'static) {
+//!     let signing = SigningLayer::new(Auth::new("key".into(), "id".into()));
+//!
+//!     let service = TraceLayer.layer(
+//!         RetryLayer::new(RetryPolicy::default()).layer(
+//!             TimeoutLayer::new(Duration::from_secs(10))
+//!                 .layer(signing.layer(TransportService::new(transport))),
+//!         ),
+//!     );
+//! }
+//! ```
+
+use std::fmt::Debug;
+use std::mem;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{ready, Context, Poll};
+use std::time::{Duration, Instant};
+
+use futures_timer::Delay;
+use futures_util::future::{poll_fn, BoxFuture};
+use futures_util::FutureExt;
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::body::Bytes;
+use hyper::header::HeaderValue;
+use hyper::{HeaderMap, Method, Request, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use time::OffsetDateTime;
+use tower_layer::Layer;
+use tower_service::Service;
+use tracing::{debug, debug_span, instrument, trace, Instrument};
+
+use crate::client::{Language, ResponseMeta};
+use crate::clock::ClockSkew;
+use crate::credential::CredentialProvider;
+use crate::endpoint::Endpoint;
+use crate::error::{ApiError, AuthFailure, CommonError, Error, ErrorCode, HttpStatusError};
+use crate::retry::RetryPolicy;
+use crate::transport::{body_error, RequestBody, ResponseBody, Transport};
+use crate::{tc3_hmac, timer};
+
+#[cfg(doc)]
+use crate::client::{Client, ClientBuilder};
+
+/// the default `User-Agent` header
+const DEFAULT_USER_AGENT: &str = concat!("tencentcloud-rs/", env!("CARGO_PKG_VERSION"));
+
+/// the http request signed by the [`SigningLayer`]
+pub type SignedRequest = Request<RequestBody>;
+
+/// the api request before signing, created by the [`Client::api_request`]
+#[derive(Debug, Clone)]
+pub struct ApiRequest {
+    pub(crate) service: String,
+    pub(crate) version: String,
+    pub(crate) action: String,
+    pub(crate) region: String,
+    pub(crate) endpoint: Endpoint,
+    pub(crate) skip_sign: bool,
+    pub(crate) language: Option<Language>,
+    pub(crate) headers: HeaderMap,
+    pub(crate) payload: Bytes,
+    pub(crate) total_timeout: Option<Duration>,
+    pub(crate) response_size_limit: Option<usize>,
+}
+
+impl ApiRequest {
+    /// the service, for example: `cvm`
+    pub fn service(&self) -> &str {
+        &self.service
+    }
+
+    /// the api version, for example: `2017-03-12`
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// the action, for example: `DescribeInstances`
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+
+    /// the region, empty if the api is not regional
+    pub fn region(&self) -> &str {
+        &self.region
+    }
+
+    /// the resolved endpoint
+    pub fn endpoint(&self) -> &Endpoint {
+        &self.endpoint
+    }
+
+    /// the json payload
+    pub fn payload(&self) -> &Bytes {
+        &self.payload
+    }
+}
+
+/// the api response whose error is checked, the body is deserialized by
+/// [`ApiResponse::deserialize`]
+#[derive(Debug)]
+pub struct ApiResponse {
+    pub(crate) meta: ResponseMeta,
+    body: Bytes,
+}
+
+impl ApiResponse {
+    /// the [`ResponseMeta`]
+    pub fn meta(&self) -> &ResponseMeta {
+        &self.meta
+    }
+
+    /// the json body
+    pub fn body(&self) -> &Bytes {
+        &self.body
+    }
+
+    /// deserialize the `Response` object of the body
+    pub fn deserialize<T: DeserializeOwned>(self) -> Result<(T, ResponseMeta), Error> {
+        let response = serde_json::from_slice::<Response<T>>(&self.body)?;
+
+        trace!("unmarshal response done");
+
+        let mut meta = self.meta;
+        meta.request_id = response.response.request_id;
+
+        match response.response.response {
+            None => Err(Error::Other("miss response".into())),
+            Some(resp) => Ok((resp, meta)),
+        }
+    }
+}
+
+/// the [`Transport`] as the innermost service
+#[derive(Debug, Clone)]
+pub struct TransportService {
+    transport: Arc<dyn Transport>,
+}
+
+impl TransportService {
+    /// create the service which sends the request by the `transport`
+    pub fn new(transport: impl Transport + 'static) -> Self {
+        Self::with_transport(Arc::new(transport))
+    }
+
+    pub(crate) fn with_transport(transport: Arc<dyn Transport>) -> Self {
+        Self { transport }
+    }
+}
+
+impl Service<SignedRequest> for TransportService {
+    type Response = hyper::Response<ResponseBody>;
+    type Error = Error;
+    type Future = BoxFuture<'static, Result<hyper::Response<ResponseBody>, Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: SignedRequest) -> Self::Future {
+        let transport = self.transport.clone();
+
+        async move { transport.send(request).await }.boxed()
+    }
+}
+
+/// sign the [`ApiRequest`] by the credential of the [`CredentialProvider`]
+///
+/// the clock skew is corrected by the `Date` header of the responses, the request failed with
+/// `AuthFailure.SignatureExpire` is re-signed and re-sent once after the offset is corrected
+#[derive(Debug, Clone)]
+pub struct SigningLayer {
+    credential_provider: Arc<dyn CredentialProvider>,
+    user_agent: String,
+    clock_skew: Option<ClockSkew>,
+}
+
+impl SigningLayer {
+    /// create the layer which signs the request by the `credential_provider`
+    pub fn new(credential_provider: impl CredentialProvider + 'static) -> Self {
+        Self::with_provider(Arc::new(credential_provider))
+    }
+
+    pub(crate) fn with_provider(credential_provider: Arc<dyn CredentialProvider>) -> Self {
+        Self {
+            credential_provider,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            clock_skew: Some(ClockSkew::default()),
+        }
+    }
+
+    /// set the `User-Agent` header, default is `tencentcloud-rs/<version>`
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+
+        self
+    }
+
+    /// enable or disable the clock skew correction, it is enabled by default
+    pub fn with_clock_skew_correction(mut self, enable: bool) -> Self {
+        self.clock_skew = enable.then(ClockSkew::default);
+
+        self
+    }
+}
+
+impl<S> Layer<S> for SigningLayer {
+    type Service = Signing<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Signing {
+            layer: self.clone(),
+            inner,
+        }
+    }
+}
+
+/// the service of the [`SigningLayer`]
+#[derive(Debug, Clone)]
+pub struct Signing<S> {
+    layer: SigningLayer,
+    inner: S,
+}
+
+impl<S> Service<ApiRequest> for Signing<S>
+where
+    S: Service<SignedRequest, Response = hyper::Response<ResponseBody>, Error = Error>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send,
+{
+    type Response = ApiResponse;
+    type Error = Error;
+    type Future = BoxFuture<'static, Result<ApiResponse, Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: ApiRequest) -> Self::Future {
+        // take the ready inner service, leave a clone for the next call
+        let clone = self.inner.clone();
+        let inner = mem::replace(&mut self.inner, clone);
+        let layer = self.layer.clone();
+
+        async move { layer.send(inner, &request).await }.boxed()
+    }
+}
+
+impl SigningLayer {
+    async fn send<S>(&self, mut inner: S, request: &ApiRequest) -> Result<ApiResponse, Error>
+    where
+        S: Service<SignedRequest, Response = hyper::Response<ResponseBody>, Error = Error>,
+    {
+        let offset_secs = self.clock_skew.as_ref().map(ClockSkew::offset_secs);
+
+        let err = match self.send_once(&mut inner, request).await {
+            Ok(response) => return Ok(response),
+            Err(err) => err,
+        };
+
+        // the local clock is skewed, re-send once with the corrected timestamp
+        if !is_signature_expire(&err)
+            || offset_secs == self.clock_skew.as_ref().map(ClockSkew::offset_secs)
+        {
+            return Err(err);
+        }
+
+        debug!(%err, "signature expired because of clock skew, re-send it");
+
+        poll_fn(|cx| inner.poll_ready(cx)).await?;
+        let mut response = self.send_once(&mut inner, request).await?;
+        response.meta.attempts += 1;
+
+        Ok(response)
+    }
+
+    async fn send_once<S>(&self, inner: &mut S, request: &ApiRequest) -> Result<ApiResponse, Error>
+    where
+        S: Service<SignedRequest, Response = hyper::Response<ResponseBody>, Error = Error>,
+    {
+        let signed = self.create_request(request).await?;
+
+        trace!(?signed, "create http request done");
+
+        let response = inner.call(signed).await?;
+
+        trace!(?response, "get http response done");
+
+        if let Some(clock_skew) = &self.clock_skew {
+            clock_skew.observe(response.headers());
+        }
+
+        if response.status() != StatusCode::OK {
+            return Err(http_status_error(response).await);
+        }
+
+        let (parts, body) = response.into_parts();
+        // box the limited body, otherwise rustc fails to prove the future is Send
+        let body = match request.response_size_limit {
+            None => body.collect().await,
+            Some(limit) => Limited::new(body, limit).boxed().collect().await,
+        };
+        let body = body.map_err(body_error)?.to_bytes();
+
+        trace!("read http body done");
+
+        let envelope = serde_json::from_slice::<ErrorEnvelope>(&body)?;
+        let request_id = envelope.response.request_id.unwrap_or_default();
+        if let Some(err) = envelope.response.error {
+            return Err(Error::Api { err, request_id });
+        }
+
+        let meta = ResponseMeta {
+            request_id,
+            status: parts.status,
+            headers: parts.headers,
+            version: parts.version,
+            attempts: 1,
+            latency: Duration::ZERO,
+        };
+
+        Ok(ApiResponse { meta, body })
+    }
+
+    #[instrument(level = "trace", skip(self), err)]
+    pub(crate) async fn create_request(
+        &self,
+        request: &ApiRequest,
+    ) -> Result<SignedRequest, Error> {
+        let endpoint = &request.endpoint;
+        let now = match &self.clock_skew {
+            None => OffsetDateTime::now_utc(),
+            Some(clock_skew) => clock_skew.now(),
+        };
+        let (authorization, token) = if request.skip_sign {
+            ("SKIP".to_string(), None)
+        } else {
+            let auth = self.credential_provider.credential().await?;
+            let authorization = tc3_hmac::calculate_authorization(
+                auth.secret_id(),
+                auth.secret_key(),
+                &request.service,
+                &endpoint.host,
+                &endpoint.path,
+                &request.payload,
+                &now,
+            )
+            .map_err(Error::Other)?;
+
+            (
+                authorization,
+                auth.token().map(sensitive_header).transpose()?,
+            )
+        };
+
+        let mut builder = Request::builder()
+            .uri(endpoint.uri())
+            .method(Method::POST)
+            .header("Authorization", sensitive_header(&authorization)?)
+            .header("Content-Type", "application/json; charset=utf-8")
+            .header("User-Agent", &self.user_agent)
+            .header("X-TC-Action", &request.action)
+            .header("X-TC-Timestamp", now.unix_timestamp())
+            .header("X-TC-Version", &request.version);
+        if !request.region.is_empty() {
+            builder = builder.header("X-TC-Region", &request.region);
+        }
+        if let Some(token) = token {
+            builder = builder.header("X-TC-Token", token);
+        }
+        if let Some(language) = request.language {
+            builder = builder.header("X-TC-Language", language.as_str());
+        }
+        for (name, value) in &request.headers {
+            builder = builder.header(name, value);
+        }
+
+        builder
+            .body(Full::from(request.payload.clone()))
+            .map_err(|err| Error::Other(err.into()))
+    }
+}
+
+/// the header value which is hidden from the `Debug` output, so the signature and the session
+/// token are not logged with the request
+fn sensitive_header(value: &str) -> Result<HeaderValue, Error> {
+    let mut value = HeaderValue::from_str(value).map_err(|err| Error::Other(err.into()))?;
+    value.set_sensitive(true);
+
+    Ok(value)
+}
+
+/// retry the failed api call by the [`RetryPolicy`]
+#[derive(Debug, Clone)]
+pub struct RetryLayer {
+    policy: RetryPolicy,
+}
+
+impl RetryLayer {
+    /// create the layer which retries by the `policy`
+    pub fn new(policy: RetryPolicy) -> Self {
+        Self { policy }
+    }
+}
+
+impl<S> Layer<S> for RetryLayer {
+    type Service = Retry<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Retry {
+            policy: self.policy.clone(),
+            inner,
+        }
+    }
+}
+
+/// the service of the [`RetryLayer`]
+#[derive(Debug, Clone)]
+pub struct Retry<S> {
+    policy: RetryPolicy,
+    inner: S,
+}
+
+impl<S> Service<ApiRequest> for Retry<S>
+where
+    S: Service<ApiRequest, Response = ApiResponse, Error = Error> + Clone + Send + 'static,
+    S::Future: Send,
+{
+    type Response = ApiResponse;
+    type Error = Error;
+    type Future = BoxFuture<'static, Result<ApiResponse, Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: ApiRequest) -> Self::Future {
+        // take the ready inner service, leave a clone for the next call
+        let clone = self.inner.clone();
+        let mut inner = mem::replace(&mut self.inner, clone);
+        let policy = self.policy.clone();
+
+        async move {
+            let mut attempt = 1;
+            loop {
+                let err = match inner.call(request.clone()).await {
+                    Ok(mut response) => {
+                        policy.on_success();
+                        response.meta.attempts += attempt - 1;
+
+                        return Ok(response);
+                    }
+
+                    Err(err) => err,
+                };
+
+                match policy.should_retry(attempt, &err) {
+                    None => return Err(err),
+                    Some(delay) => {
+                        debug!(attempt, ?delay, %err, "api request failed, retry it");
+
+                        timer::sleep(delay).await;
+                        attempt += 1;
+
+                        poll_fn(|cx| inner.poll_ready(cx)).await?;
+                    }
+                }
+            }
+        }
+        .boxed()
+    }
+}
+
+/// fail the call with [`Error::Timeout`] if it is not completed in the timeout, `None` means no
+/// timeout
+#[derive(Debug, Copy, Clone)]
+pub struct TimeoutLayer {
+    timeout: Option<Duration>,
+}
+
+impl TimeoutLayer {
+    /// create the layer with the `timeout`
+    pub fn new(timeout: impl Into<Option<Duration>>) -> Self {
+        Self {
+            timeout: timeout.into(),
+        }
+    }
+}
+
+impl<S> Layer<S> for TimeoutLayer {
+    type Service = Timeout<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Timeout {
+            timeout: self.timeout,
+            inner,
+        }
+    }
+}
+
+/// the service of the [`TimeoutLayer`]
+#[derive(Debug, Clone)]
+pub struct Timeout<S> {
+    timeout: Option<Duration>,
+    inner: S,
+}
+
+impl<S, R> Service<R> for Timeout<S>
+where
+    S: Service<R, Error = Error>,
+    S::Response: Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = BoxFuture<'static, Result<S::Response, Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let response = self.inner.call(request);
+        let timeout = self.timeout;
+
+        async move {
+            timer::maybe_timeout(timeout, response)
+                .await
+                .map_err(|_| Error::Timeout)?
+        }
+        .boxed()
+    }
+}
+
+/// limit the calls to `num` calls per `per` duration, the call exceeding the limit waits until
+/// the next period; the clones of the service share the limit
+#[derive(Debug, Copy, Clone)]
+pub struct RateLimitLayer {
+    rate: Option<(u64, Duration)>,
+}
+
+impl RateLimitLayer {
+    /// create the layer which allows `num` calls per `per` duration, `0` is treated as `1`
+    pub fn new(num: u64, per: Duration) -> Self {
+        Self {
+            rate: Some((num.max(1), per)),
+        }
+    }
+
+    /// the layer which doesn't limit the calls
+    pub(crate) fn unlimited() -> Self {
+        Self { rate: None }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        let bucket = self.rate.map(|(num, per)| {
+            Arc::new(Mutex::new(Bucket {
+                num,
+                per,
+                remaining: num,
+                refill_at: Instant::now() + per,
+            }))
+        });
+
+        RateLimit {
+            inner,
+            bucket,
+            permit: false,
+            delay: None,
+        }
+    }
+}
+
+/// the calls allowed in the current period
+#[derive(Debug)]
+struct Bucket {
+    num: u64,
+    per: Duration,
+    remaining: u64,
+    refill_at: Instant,
+}
+
+impl Bucket {
+    /// take a permit, or return how long to wait for the next period
+    fn acquire(&mut self, now: Instant) -> Option<Duration> {
+        if now >= self.refill_at {
+            self.remaining = self.num;
+            self.refill_at = now + self.per;
+        }
+
+        if self.remaining == 0 {
+            return Some(self.refill_at - now);
+        }
+
+        self.remaining -= 1;
+
+        None
+    }
+}
+
+/// the service of the [`RateLimitLayer`]
+#[derive(Debug)]
+pub struct RateLimit<S> {
+    inner: S,
+    bucket: Option<Arc<Mutex<Bucket>>>,
+    permit: bool,
+    delay: Option<Delay>,
+}
+
+impl<S: Clone> Clone for RateLimit<S> {
+    fn clone(&self) -> Self {
+        // the permit is not shared, the clone acquires its own one
+        Self {
+            inner: self.inner.clone(),
+            bucket: self.bucket.clone(),
+            permit: false,
+            delay: None,
+        }
+    }
+}
+
+impl<S, R> Service<R> for RateLimit<S>
+where
+    S: Service<R>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if let Some(bucket) = &self.bucket {
+            while !self.permit {
+                if let Some(delay) = &mut self.delay {
+                    ready!(delay.poll_unpin(cx));
+                    self.delay = None;
+                }
+
+                let wait = bucket
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .acquire(Instant::now());
+                match wait {
+                    None => self.permit = true,
+                    Some(wait) => {
+                        trace!(?wait, "rate limited, wait for the next period");
+
+                        self.delay = Some(Delay::new(wait));
+                    }
+                }
+            }
+        }
+
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        self.permit = false;
+
+        self.inner.call(request)
+    }
+}
+
+/// run the api call in the `api_call` tracing span, which records the service, action and region
+#[derive(Debug, Copy, Clone, Default)]
+pub struct TraceLayer;
+
+impl<S> Layer<S> for TraceLayer {
+    type Service = Trace<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Trace { inner }
+    }
+}
+
+/// the service of the [`TraceLayer`]
+#[derive(Debug, Clone)]
+pub struct Trace<S> {
+    inner: S,
+}
+
+impl<S> Service<ApiRequest> for Trace<S>
+where
+    S: Service<ApiRequest, Response = ApiResponse, Error = Error>,
+    S::Future: Send + 'static,
+{
+    type Response = ApiResponse;
+    type Error = Error;
+    type Future = BoxFuture<'static, Result<ApiResponse, Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: ApiRequest) -> Self::Future {
+        let span = debug_span!(
+            "api_call",
+            service = %request.service,
+            action = %request.action,
+            region = %request.region,
+        );
+        let response = span.in_scope(|| self.inner.call(request));
+
+        async move {
+            match response.await {
+                Ok(response) => {
+                    debug!(
+                        request_id = %response.meta.request_id,
+                        attempts = response.meta.attempts,
+                        "api call done"
+                    );
+
+                    Ok(response)
+                }
+
+                Err(err) => {
+                    debug!(%err, "api call failed");
+
+                    Err(err)
+                }
+            }
+        }
+        .instrument(span)
+        .boxed()
+    }
+}
+
+/// the response headers kept in the [`HttpStatusError`]
+const HTTP_STATUS_HEADERS: &[&str] = &[
+    "content-type",
+    "content-length",
+    "date",
+    "server",
+    "retry-after",
+    "x-request-id",
+    "x-tc-requestid",
+];
+
+/// collect the [`HttpStatusError`] from the not OK `response`
+///
+/// the body is read until [`HttpStatusError::BODY_SNIPPET_LIMIT`], the truncated text body
+/// doesn't end with a partial utf-8 character; a failed read keeps the bytes read so far
+async fn http_status_error(response: hyper::Response<ResponseBody>) -> Error {
+    let status = response.status();
+    let mut headers = HeaderMap::new();
+    for name in HTTP_STATUS_HEADERS {
+        for value in response.headers().get_all(*name) {
+            headers.append(*name, value.clone());
+        }
+    }
+
+    let mut body = response.into_body();
+    let mut snippet = Vec::new();
+    let mut truncated = false;
+    while let Some(Ok(frame)) = body.frame().await {
+        let Ok(chunk) = frame.into_data() else {
+            continue;
+        };
+
+        let remaining = HttpStatusError::BODY_SNIPPET_LIMIT - snippet.len();
+        if chunk.len() > remaining {
+            snippet.extend_from_slice(&chunk[..remaining]);
+            truncated = true;
+
+            // don't split the last utf-8 character of the text body
+            if let Err(err) = std::str::from_utf8(&snippet) {
+                if err.error_len().is_none() {
+                    snippet.truncate(err.valid_up_to());
+                }
+            }
+
+            break;
+        }
+
+        snippet.extend_from_slice(&chunk);
+    }
+
+    let (request_id, api_error) = match serde_json::from_slice::<ErrorEnvelope>(&snippet) {
+        Err(_) => (None, None),
+        Ok(envelope) => (envelope.response.request_id, envelope.response.error),
+    };
+
+    let err = HttpStatusError {
+        status,
+        headers,
+        body: snippet.into(),
+        truncated,
+        request_id,
+        api_error,
+    };
+
+    trace!(?err, "http status is not OK");
+
+    Error::HttpStatus(Box::new(err))
+}
+
+fn is_signature_expire(err: &Error) -> bool {
+    matches!(
+        err.code().map(ErrorCode::kind),
+        Some(CommonError::AuthFailure(AuthFailure::SignatureExpire))
+    )
+}
+
+#[derive(Debug, Deserialize)]
+struct Response<T> {
+    #[serde(rename = "Response")]
+    response: ResponseDetail<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseDetail<T> {
+    #[serde(rename = "RequestId")]
+    request_id: String,
+
+    #[serde(flatten)]
+    response: Option<T>,
+}
+
+/// the tencentcloud error envelope of the response
+#[derive(Debug, Deserialize)]
+struct ErrorEnvelope {
+    #[serde(rename = "Response")]
+    response: ErrorEnvelopeDetail,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorEnvelopeDetail {
+    #[serde(rename = "RequestId")]
+    request_id: Option<String>,
+
+    #[serde(rename = "Error")]
+    error: Option<ApiError>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::pending;
+    use std::time::SystemTime;
+
+    use hyper::header::HeaderName;
+    use serde_json::json;
+
+    use super::*;
+    use crate::client::{Auth, RequestOptions};
+    use crate::test_util::{self, MockTransport, TestApi, TestRequest, TestResponse};
+
+    #[tokio::test]
+    async fn client_as_service() {
+        let transport = MockTransport::new();
+        transport.push_ok(json!({ "Status": "OK" }));
+        let mut client = test_util::builder(transport.clone()).build().unwrap();
+
+        let request = client
+            .api_request::<TestApi>(
+                &TestRequest::default(),
+                &RequestOptions::new().with_region("ap-beijing"),
+            )
+            .unwrap();
+        assert_eq!(request.action(), "TestAction");
+        assert_eq!(request.region(), "ap-beijing");
+
+        let response = client.call(request).await.unwrap();
+        let (resp, meta) = response.deserialize::<TestResponse>().unwrap();
+
+        assert_eq!(resp.status.as_deref(), Some("OK"));
+        assert_eq!(meta.request_id, "request-id");
+        assert_eq!(meta.attempts, 1);
+        assert_eq!(transport.requests()[0].headers["X-TC-Region"], "ap-beijing");
+    }
+
+    #[tokio::test]
+    async fn compose_layers() {
+        let transport = MockTransport::new();
+        transport
+            .push_api_error("InternalError")
+            .push_ok(json!({ "Status": "OK" }));
+        let client = test_util::builder(MockTransport::new()).build().unwrap();
+        let request = client
+            .api_request::<TestApi>(&TestRequest::default(), &RequestOptions::new())
+            .unwrap();
+
+        let signing = SigningLayer::new(Auth::new("key".to_string(), "id".to_string()))
+            .with_user_agent("test-agent");
+        let mut service = TraceLayer.layer(
+            RetryLayer::new(RetryPolicy::default().with_backoff(Duration::ZERO, Duration::ZERO))
+                .layer(
+                    TimeoutLayer::new(Duration::from_secs(10))
+                        .layer(signing.layer(TransportService::new(transport.clone()))),
+                ),
+        );
+
+        poll_fn(|cx| service.poll_ready(cx)).await.unwrap();
+        let response = service.call(request).await.unwrap();
+
+        assert_eq!(response.meta().attempts, 2);
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        for request in requests {
+            assert_eq!(request.headers["User-Agent"], "test-agent");
+            assert!(request.headers["Authorization"]
+                .to_str()
+                .unwrap()
+                .starts_with("TC3-HMAC-SHA256 Credential=id/"));
+        }
+    }
+
+    #[tokio::test]
+    async fn client_rate_limit() {
+        let transport = MockTransport::new();
+        for _ in 0..3 {
+            transport.push_ok(json!({ "Status": "OK" }));
+        }
+        let client = test_util::builder(transport)
+            .rate_limit(2, Duration::from_millis(200))
+            .build()
+            .unwrap();
+
+        let start = Instant::now();
+        for _ in 0..2 {
+            client
+                .send::<TestApi>(&TestRequest::default())
+                .await
+                .unwrap();
+        }
+        assert!(start.elapsed() < Duration::from_millis(100));
+
+        // the limit is shared by the clones
+        client
+            .clone()
+            .send::<TestApi>(&TestRequest::default())
+            .await
+            .unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(150));
+    }
+
+    #[tokio::test]
+    async fn sign_and_send() {
+        let transport = MockTransport::new();
+        transport.push_ok(json!({ "Status": "OK" }));
+        let mut client = test_util::builder(transport.clone()).build().unwrap();
+
+        let request = client
+            .api_request::<TestApi>(&TestRequest::default(), &RequestOptions::new())
+            .unwrap();
+        let signed = client.sign(&request).await.unwrap();
+        assert_eq!(signed.headers()["X-TC-Action"], "TestAction");
+
+        poll_fn(|cx| Service::<SignedRequest>::poll_ready(&mut client, cx))
+            .await
+            .unwrap();
+        let response = client.call(signed).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(transport.requests()[0]
+            .headers
+            .contains_key("Authorization"));
+    }
+
+    #[tokio::test]
+    async fn credential_is_not_logged() {
+        let client = test_util::builder(MockTransport::new()).build().unwrap();
+        let request = client
+            .api_request::<TestApi>(&TestRequest::default(), &RequestOptions::new())
+            .unwrap();
+        let signing = SigningLayer::new(
+            Auth::new("key".to_string(), "id".to_string()).with_token("session-token".to_string()),
+        );
+
+        let signed = signing.create_request(&request).await.unwrap();
+
+        assert_eq!(signed.headers()["X-TC-Token"], "session-token");
+        assert!(signed.headers()["X-TC-Token"].is_sensitive());
+        assert!(signed.headers()["Authorization"].is_sensitive());
+        let logged = format!("{signed:?}");
+        assert!(!logged.contains("session-token"), "{logged}");
+        assert!(!logged.contains("Signature="), "{logged}");
+    }
+
+    #[tokio::test]
+    async fn request_options_headers() {
+        let transport = MockTransport::new();
+        transport.push_ok(json!({}));
+        let client = test_util::builder(transport.clone()).build().unwrap();
+
+        for name in [
+            "x-tc-region",
+            "x-tc-action",
+            "x-tc-token",
+            "authorization",
+            "x-trace-id",
+        ] {
+            let options = RequestOptions::new().with_header(
+                HeaderName::from_static(name),
+                HeaderValue::from_static("value"),
+            );
+
+            let err = client
+                .api_request::<TestApi>(&TestRequest::default(), &options)
+                .unwrap_err();
+            assert!(err.to_string().contains(name), "{err}");
+        }
+
+        let options = RequestOptions::new().with_header(
+            HeaderName::from_static("x-tc-traceid"),
+            HeaderValue::from_static("trace"),
+        );
+        client
+            .send_with::<TestApi>(&TestRequest::default(), &options)
+            .await
+            .unwrap();
+        assert_eq!(transport.requests()[0].headers["X-TC-TraceId"], "trace");
+    }
+
+    #[tokio::test]
+    async fn request_options_language() {
+        let transport = MockTransport::new();
+        transport.push_ok(json!({})).push_ok(json!({}));
+        let client = test_util::builder(transport.clone()).build().unwrap();
+
+        client
+            .send::<TestApi>(&TestRequest::default())
+            .await
+            .unwrap();
+        client
+            .send_with::<TestApi>(
+                &TestRequest::default(),
+                &RequestOptions::new().with_language(Language::EnUs),
+            )
+            .await
+            .unwrap();
+
+        let requests = transport.requests();
+        assert!(!requests[0].headers.contains_key("X-TC-Language"));
+        assert_eq!(requests[1].headers["X-TC-Language"], "en-US");
+    }
+
+    #[tokio::test]
+    async fn request_options_response_size_limit() {
+        let transport = MockTransport::new();
+        let large = json!({ "Status": "x".repeat(1024) });
+        transport.push_ok(large.clone()).push_ok(large);
+        let client = test_util::builder(transport.clone())
+            .retry_policy(RetryPolicy::disabled())
+            .build()
+            .unwrap();
+
+        let err = client
+            .send_with::<TestApi>(
+                &TestRequest::default(),
+                &RequestOptions::new().with_response_size_limit(512),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("length limit exceeded"), "{err}");
+
+        let (resp, _) = client
+            .send_with::<TestApi>(
+                &TestRequest::default(),
+                &RequestOptions::new().with_response_size_limit(4096),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.status.unwrap().len(), 1024);
+    }
+
+    #[tokio::test]
+    async fn builder_defaults_and_overrides() {
+        let transport = MockTransport::new();
+        let large = json!({ "Status": "x".repeat(1024) });
+        transport
+            .push_ok(large.clone())
+            .push_ok(large.clone())
+            .push_ok(large);
+        let client = test_util::builder(transport.clone())
+            .language(Language::ZhCn)
+            .response_size_limit(512)
+            .retry_policy(RetryPolicy::disabled())
+            .build()
+            .unwrap();
+
+        // the builder defaults apply to every call
+        let err = client
+            .send::<TestApi>(&TestRequest::default())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("length limit exceeded"), "{err}");
+
+        // the request options override the builder defaults
+        let options = RequestOptions::new()
+            .with_language(Language::EnUs)
+            .with_response_size_limit(4096);
+        client
+            .send_with::<TestApi>(&TestRequest::default(), &options)
+            .await
+            .unwrap();
+
+        // the options which don't set them keep the builder defaults
+        let err = client
+            .send_with::<TestApi>(&TestRequest::default(), &RequestOptions::new())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("length limit exceeded"), "{err}");
+
+        let languages = transport
+            .requests()
+            .iter()
+            .map(|request| request.headers["X-TC-Language"].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(languages, ["zh-CN", "en-US", "zh-CN"]);
+    }
+
+    #[tokio::test]
+    async fn request_options_deadline() {
+        let transport = MockTransport::new();
+        let client = test_util::builder(transport.clone())
+            .total_timeout(Duration::from_secs(10))
+            .build()
+            .unwrap();
+        let total_timeout = |options: &RequestOptions| {
+            client
+                .api_request::<TestApi>(&TestRequest::default(), options)
+                .unwrap()
+                .total_timeout
+                .unwrap()
+        };
+
+        assert_eq!(
+            total_timeout(&RequestOptions::new()),
+            Duration::from_secs(10)
+        );
+
+        // the earlier one of the deadline and the client total timeout is used
+        let deadline = Instant::now() + Duration::from_secs(1);
+        assert!(
+            total_timeout(&RequestOptions::new().with_deadline(deadline)) <= Duration::from_secs(1)
+        );
+        let deadline = Instant::now() + Duration::from_secs(60);
+        assert_eq!(
+            total_timeout(&RequestOptions::new().with_deadline(deadline)),
+            Duration::from_secs(10)
+        );
+
+        // the passed deadline fails the call without sending it
+        let options =
+            RequestOptions::new().with_deadline(Instant::now() - Duration::from_millis(1));
+        let err = client
+            .send_with::<TestApi>(&TestRequest::default(), &options)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Timeout), "{err:?}");
+        assert!(transport.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn response_meta() {
+        let transport = MockTransport::new();
+        let headers = HeaderMap::from_iter([(
+            HeaderName::from_static("x-custom"),
+            HeaderValue::from_static("custom"),
+        )]);
+        transport.push_api_error("InternalError").push_text(
+            StatusCode::OK,
+            headers,
+            json!({ "Response": { "Status": "OK", "RequestId": "meta-request-id" } }).to_string(),
+        );
+        let client = test_util::builder(transport)
+            .retry_policy(RetryPolicy::default().with_backoff(Duration::ZERO, Duration::ZERO))
+            .build()
+            .unwrap();
+
+        let start = Instant::now();
+        let (resp, meta) = client
+            .send_with_meta::<TestApi>(&TestRequest::default())
+            .await
+            .unwrap();
+        let elapsed = start.elapsed();
+
+        assert_eq!(resp.status.as_deref(), Some("OK"));
+        assert_eq!(meta.request_id, "meta-request-id");
+        assert_eq!(meta.status, StatusCode::OK);
+        assert_eq!(meta.headers["x-custom"], "custom");
+        assert_eq!(meta.version, hyper::Version::HTTP_11);
+        assert_eq!(meta.attempts, 2);
+
+        // the latency is the wall-clock time of the whole call, including the retry
+        assert!(meta.latency > Duration::ZERO);
+        assert!(meta.latency <= elapsed, "{:?} > {elapsed:?}", meta.latency);
+    }
+
+    #[tokio::test]
+    async fn call_raw_api() {
+        let transport = MockTransport::new();
+        transport
+            .push_ok(json!({ "TargetText": "hello" }))
+            .push_ok(json!({}));
+        let client = test_util::builder(transport.clone()).build().unwrap();
+        let request = json!({ "SourceText": "你好", "Source": "zh", "ProjectId": 0 });
+
+        let response = client
+            .call_raw(
+                "tmt",
+                "2018-03-21",
+                "TextTranslate",
+                "tmt.tencentcloudapi.com",
+                "ap-beijing",
+                request.clone(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            response,
+            json!({ "TargetText": "hello", "RequestId": "request-id" })
+        );
+
+        let recorded = &transport.requests()[0];
+        assert_eq!(recorded.body, request);
+        assert_eq!(recorded.uri.host(), Some("tmt.tencentcloudapi.com"));
+        assert_eq!(recorded.headers["X-TC-Action"], "TextTranslate");
+        assert_eq!(recorded.headers["X-TC-Version"], "2018-03-21");
+        assert_eq!(recorded.headers["X-TC-Region"], "ap-beijing");
+
+        // the signature covers the json payload
+        let timestamp = timestamp(recorded);
+        let authorization = tc3_hmac::calculate_authorization(
+            "id",
+            "key",
+            "tmt",
+            "tmt.tencentcloudapi.com",
+            "/",
+            &serde_json::to_vec(&request).unwrap(),
+            &OffsetDateTime::from_unix_timestamp(timestamp).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(recorded.headers["Authorization"], authorization.as_str());
+
+        // the empty region means the api is not regional
+        client
+            .call_raw(
+                "cam",
+                "2019-01-16",
+                "GetUserAppId",
+                "cam.tencentcloudapi.com",
+                "",
+                json!({}),
+            )
+            .await
+            .unwrap();
+        assert!(!transport.requests()[1].headers.contains_key("X-TC-Region"));
+    }
+
+    fn status_response(
+        status: StatusCode,
+        headers: &[(&str, &str)],
+        body: impl Into<Bytes>,
+    ) -> hyper::Response<ResponseBody> {
+        let mut response = hyper::Response::builder().status(status);
+        for (name, value) in headers {
+            response = response.header(*name, *value);
+        }
+
+        response
+            .body(
+                Full::new(body.into())
+                    .map_err(|never| match never {})
+                    .boxed(),
+            )
+            .unwrap()
+    }
+
+    async fn status_error(response: hyper::Response<ResponseBody>) -> HttpStatusError {
+        match http_status_error(response).await {
+            Error::HttpStatus(err) => *err,
+            err => panic!("unexpected error: {err:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn http_status_headers() {
+        let err = status_error(status_response(
+            StatusCode::TOO_MANY_REQUESTS,
+            &[
+                ("Content-Type", "text/plain"),
+                ("Retry-After", "1"),
+                ("X-TC-RequestId", "request-id"),
+                ("Set-Cookie", "session=secret"),
+                ("X-Custom", "custom"),
+            ],
+            "",
+        ))
+        .await;
+
+        assert_eq!(err.status, StatusCode::TOO_MANY_REQUESTS);
+        let mut names = err
+            .headers
+            .keys()
+            .map(|name| name.as_str())
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        assert_eq!(names, ["content-type", "retry-after", "x-tc-requestid"]);
+        assert_eq!(err.headers["retry-after"], "1");
+        assert!(err.body.is_empty());
+        assert!(!err.truncated);
+    }
+
+    #[tokio::test]
+    async fn http_status_body_snippet() {
+        let limit = HttpStatusError::BODY_SNIPPET_LIMIT;
+
+        let body = "a".repeat(limit);
+        let err = status_error(status_response(StatusCode::BAD_GATEWAY, &[], body.clone())).await;
+        assert_eq!(err.body, body);
+        assert!(!err.truncated);
+
+        let err = status_error(status_response(
+            StatusCode::BAD_GATEWAY,
+            &[],
+            "a".repeat(limit + 1),
+        ))
+        .await;
+        assert_eq!(err.body.len(), limit);
+        assert!(err.truncated);
+
+        // the 3 bytes character crosses the limit, it is dropped rather than split
+        let body = format!("{}中文", "a".repeat(limit - 1));
+        let err = status_error(status_response(StatusCode::BAD_GATEWAY, &[], body)).await;
+        assert_eq!(err.body, "a".repeat(limit - 1));
+        assert!(err.truncated);
+
+        // the binary body is cut at the limit
+        let err = status_error(status_response(
+            StatusCode::BAD_GATEWAY,
+            &[],
+            vec![0xff; limit + 1],
+        ))
+        .await;
+        assert_eq!(err.body.len(), limit);
+    }
+
+    #[tokio::test]
+    async fn http_status_error_envelope() {
+        let body = json!({
+            "Response": {
+                "Error": { "Code": "RequestLimitExceeded", "Message": "too many requests" },
+                "RequestId": "request-id",
+            }
+        });
+        let err = http_status_error(status_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            &[],
+            body.to_string(),
+        ))
+        .await;
+
+        assert_eq!(err.code().unwrap().as_str(), "RequestLimitExceeded");
+        let Error::HttpStatus(err) = err else {
+            panic!("unexpected error: {err:?}");
+        };
+        assert_eq!(err.request_id.as_deref(), Some("request-id"));
+        assert_eq!(err.api_error.unwrap().message, "too many requests");
+
+        let err = status_error(status_response(
+            StatusCode::BAD_GATEWAY,
+            &[],
+            "<html>bad gateway</html>",
+        ))
+        .await;
+        assert!(err.api_error.is_none());
+        assert!(err.request_id.is_none());
+        assert!(
+            err.to_string().contains("<html>bad gateway</html>"),
+            "{err}"
+        );
+    }
+
+    /// the `Date` header which is 1 hour ahead of the local clock
+    fn skewed_date() -> HeaderMap {
+        let date = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(3600));
+
+        HeaderMap::from_iter([(hyper::header::DATE, date.parse().unwrap())])
+    }
+
+    fn signature_expire() -> String {
+        json!({
+            "Response": {
+                "Error": { "Code": "AuthFailure.SignatureExpire", "Message": "expired" },
+                "RequestId": "request-id",
+            }
+        })
+        .to_string()
+    }
+
+    fn timestamp(request: &test_util::Recorded) -> i64 {
+        request.headers["X-TC-Timestamp"]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn resend_with_corrected_timestamp() {
+        let transport = MockTransport::new();
+        transport
+            .push_text(StatusCode::OK, skewed_date(), signature_expire())
+            .push_ok(json!({ "Status": "OK" }));
+        let client = test_util::builder(transport.clone())
+            .retry_policy(RetryPolicy::disabled())
+            .build()
+            .unwrap();
+
+        let (_, meta) = client
+            .send_with_meta::<TestApi>(&TestRequest::default())
+            .await
+            .unwrap();
+
+        assert_eq!(meta.attempts, 2);
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        let corrected = timestamp(&requests[1]) - timestamp(&requests[0]);
+        assert!((3590..=3610).contains(&corrected), "{corrected}");
+    }
+
+    #[tokio::test]
+    async fn resend_signature_expire_once() {
+        let transport = MockTransport::new();
+        transport
+            .push_text(StatusCode::OK, skewed_date(), signature_expire())
+            .push_text(StatusCode::OK, skewed_date(), signature_expire());
+        let client = test_util::builder(transport.clone())
+            .retry_policy(RetryPolicy::disabled())
+            .build()
+            .unwrap();
+
+        let err = client
+            .send::<TestApi>(&TestRequest::default())
+            .await
+            .unwrap_err();
+
+        assert!(is_signature_expire(&err), "{err:?}");
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn signature_expire_without_skew() {
+        let transport = MockTransport::new();
+        transport.push_api_error("AuthFailure.SignatureExpire");
+        let client = test_util::builder(transport.clone())
+            .retry_policy(RetryPolicy::disabled())
+            .build()
+            .unwrap();
+
+        let err = client
+            .send::<TestApi>(&TestRequest::default())
+            .await
+            .unwrap_err();
+
+        // the offset is not changed, re-sending doesn't help
+        assert!(is_signature_expire(&err), "{err:?}");
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[derive(Debug, Clone)]
+    struct Pending;
+
+    impl Service<()> for Pending {
+        type Response = ();
+        type Error = Error;
+        type Future = BoxFuture<'static, Result<(), Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _request: ()) -> Self::Future {
+            pending().boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn timeout_layer() {
+        let mut service = TimeoutLayer::new(Duration::from_millis(10)).layer(Pending);
+
+        assert!(matches!(service.call(()).await, Err(Error::Timeout)));
+    }
+}
@@ -0,0 +1,4 @@
+//! the generated service apis
+//!
+//! every service is gated behind the `service-<service>` feature, the modules are generated by
+//! the `tencentcloud-codegen` from the tencentcloud api specs
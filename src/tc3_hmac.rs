@@ -15,11 +15,11 @@ pub fn calculate_authorization(
     secret_key: &str,
     service: &str,
     host: &str,
+    path: &str,
     payload: &[u8],
     now: &OffsetDateTime,
 ) -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
     const ALGORITHM: &str = "TC3-HMAC-SHA256";
-    const CANONICAL_URI: &str = "/";
     const CANONICAL_QUERY_STRING: &str = "";
     const SIGNED_HEADERS: &str = "content-type;host";
     const FORMAT: &[FormatItem] = format_description!("[year]-[month]-[day]");
@@ -28,7 +28,7 @@ pub fn calculate_authorization(
     let hashed_request_payload = hex::encode(Sha256::digest(payload));
 
     let canonical_request = format!(
-        "POST\n{CANONICAL_URI}\n{CANONICAL_QUERY_STRING}\n{canonical_headers}\n{SIGNED_HEADERS}\n{hashed_request_payload}"
+        "POST\n{path}\n{CANONICAL_QUERY_STRING}\n{canonical_headers}\n{SIGNED_HEADERS}\n{hashed_request_payload}"
     );
 
     let date = now.format(FORMAT)?;
@@ -56,5 +56,5 @@ fn hmac_sha256(
     let mut hmac_sha256 = HmacSha256::new_from_slice(key)?;
     hmac_sha256.update(message);
 
-    Ok(hmac_sha256.finalize().into_bytes().try_into().unwrap())
+    Ok(hmac_sha256.finalize().into_bytes().into())
 }
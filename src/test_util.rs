@@ -0,0 +1,154 @@
+//! the helpers of the unit tests
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use futures_util::future::BoxFuture;
+use futures_util::FutureExt;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Bytes;
+use hyper::{HeaderMap, Request, Response, StatusCode, Uri};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::api::Api;
+use crate::client::{Auth, Client, ClientBuilder};
+use crate::error::Error;
+use crate::transport::{RequestBody, ResponseBody, Transport};
+
+/// the request received by the [`MockTransport`]
+#[derive(Debug, Clone)]
+pub struct Recorded {
+    pub uri: Uri,
+    pub headers: HeaderMap,
+    pub body: Value,
+}
+
+/// the queued response of the [`MockTransport`]
+type MockResponse = Result<(StatusCode, HeaderMap, String), Error>;
+
+/// the transport which returns the queued responses in order
+#[derive(Debug, Default)]
+pub struct MockTransport {
+    responses: Mutex<VecDeque<MockResponse>>,
+    requests: Mutex<Vec<Recorded>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// queue the api response, the `response` is the content of the `Response` object
+    pub fn push_ok(&self, response: Value) -> &Self {
+        let mut response = response;
+        response["RequestId"] = Value::String("request-id".to_string());
+
+        self.push_raw(StatusCode::OK, serde_json::json!({ "Response": response }))
+    }
+
+    /// queue the api error response
+    pub fn push_api_error(&self, code: &str) -> &Self {
+        self.push_ok(serde_json::json!({
+            "Error": { "Code": code, "Message": "mock error" }
+        }))
+    }
+
+    /// queue the raw http response
+    pub fn push_raw(&self, status: StatusCode, body: Value) -> &Self {
+        self.push_text(status, HeaderMap::new(), body.to_string())
+    }
+
+    /// queue the http response with the `headers` and the text `body`
+    pub fn push_text(
+        &self,
+        status: StatusCode,
+        headers: HeaderMap,
+        body: impl Into<String>,
+    ) -> &Self {
+        self.responses
+            .lock()
+            .unwrap()
+            .push_back(Ok((status, headers, body.into())));
+
+        self
+    }
+
+    /// the requests received so far
+    pub fn requests(&self) -> Vec<Recorded> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Transport for MockTransport {
+    fn send(
+        &self,
+        request: Request<RequestBody>,
+    ) -> BoxFuture<'_, Result<Response<ResponseBody>, Error>> {
+        async move {
+            let (parts, body) = request.into_parts();
+            let body = body.collect().await.unwrap().to_bytes();
+            self.requests.lock().unwrap().push(Recorded {
+                uri: parts.uri,
+                headers: parts.headers,
+                body: serde_json::from_slice(&body).unwrap_or(Value::Null),
+            });
+
+            let (status, headers, body) = self
+                .responses
+                .lock()
+                .unwrap()
+                .pop_front()
+                .expect("no queued mock response")?;
+
+            let mut response = Response::builder().status(status);
+            if let Some(response_headers) = response.headers_mut() {
+                response_headers.extend(headers);
+            }
+
+            Ok(response
+                .body(
+                    Full::new(Bytes::from(body))
+                        .map_err(|never| match never {})
+                        .boxed(),
+                )
+                .unwrap())
+        }
+        .boxed()
+    }
+}
+
+/// the client builder which sends the requests by the `transport`
+pub fn builder(transport: Arc<MockTransport>) -> ClientBuilder {
+    Client::builder()
+        .auth(Auth::new("key".to_string(), "id".to_string()))
+        .region("ap-guangzhou")
+        .transport(transport)
+}
+
+/// the api used by the tests
+#[derive(Debug, Copy, Clone)]
+pub struct TestApi;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TestRequest {
+    #[serde(rename = "Name", skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(rename = "ClientToken", skip_serializing_if = "Option::is_none")]
+    pub client_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TestResponse {
+    #[serde(rename = "Status", default)]
+    pub status: Option<String>,
+}
+
+impl Api for TestApi {
+    type Request = TestRequest;
+    type Response = TestResponse;
+    const VERSION: &'static str = "2017-03-12";
+    const ACTION: &'static str = "TestAction";
+    const SERVICE: &'static str = "cvm";
+    const HOST: &'static str = "cvm.tencentcloudapi.com";
+}
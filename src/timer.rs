@@ -0,0 +1,47 @@
+//! runtime agnostic timer helpers
+
+use std::future::Future;
+use std::io;
+use std::io::ErrorKind;
+use std::time::Duration;
+
+use futures_timer::Delay;
+use futures_util::future::{select, Either};
+use futures_util::pin_mut;
+
+/// the future is not completed before the deadline
+#[derive(Debug, Copy, Clone)]
+pub struct Elapsed;
+
+impl From<Elapsed> for io::Error {
+    fn from(_: Elapsed) -> Self {
+        io::Error::new(ErrorKind::TimedOut, "timeout")
+    }
+}
+
+/// wait for the `duration`
+pub async fn sleep(duration: Duration) {
+    Delay::new(duration).await
+}
+
+/// require the `fut` completes before the `duration`
+pub async fn timeout<F: Future>(duration: Duration, fut: F) -> Result<F::Output, Elapsed> {
+    let delay = Delay::new(duration);
+    pin_mut!(fut);
+
+    match select(fut, delay).await {
+        Either::Left((output, _)) => Ok(output),
+        Either::Right(_) => Err(Elapsed),
+    }
+}
+
+/// like [`timeout`], but `None` means no timeout
+pub async fn maybe_timeout<F: Future>(
+    duration: Option<Duration>,
+    fut: F,
+) -> Result<F::Output, Elapsed> {
+    match duration {
+        None => Ok(fut.await),
+        Some(duration) => timeout(duration, fut).await,
+    }
+}
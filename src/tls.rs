@@ -0,0 +1,307 @@
+//! tls configuration of the default transport
+//!
+//! the [`TlsConfig`] adds the private root certificates, such as the ca of an inspecting proxy,
+//! and the client identity for the mutual tls; or replaces the whole tls config of the backend:
+//!
+//! - `tokio-rustls-tls` and `async-std-rustls-tls`: [`TlsConfig::with_rustls_config`]
+//! - `tokio-native-tls` and `async-std-native-tls`: [`TlsConfig::with_native_tls_connector`]
+//!
+//! ## Examples:
+//!
+//! ```rust,no_run
+//! use tencentcloud::tls::TlsConfig;
+//! use tencentcloud::{Auth, Client};
+//!
+//! # fn build() -> Result<(), tencentcloud::Error> {
+//! let ca = std::fs::read("/etc/proxy/ca.pem").unwrap();
+//! let client = Client::builder()
+//!     .auth(Auth::new("key".into(), "id".into()))
+//!     .tls_config(TlsConfig::new().with_root_certificate_pem(ca))
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt::{Debug, Formatter};
+#[cfg(any(feature = "tokio-rustls-tls", feature = "async-std-rustls-tls"))]
+use std::sync::Arc;
+
+#[cfg(any(feature = "tokio-rustls-tls", feature = "async-std-rustls-tls"))]
+use rustls::pki_types::pem::PemObject;
+#[cfg(any(feature = "tokio-rustls-tls", feature = "async-std-rustls-tls"))]
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+#[cfg(any(feature = "tokio-rustls-tls", feature = "async-std-rustls-tls"))]
+use rustls::{ClientConfig, RootCertStore};
+#[cfg(any(feature = "tokio-rustls-tls", feature = "async-std-rustls-tls"))]
+use tracing::debug;
+
+#[cfg(any(
+    feature = "tokio-rustls-tls",
+    feature = "tokio-native-tls",
+    feature = "async-std-rustls-tls",
+    feature = "async-std-native-tls"
+))]
+use crate::error::Error;
+
+/// the tls config of the default transport
+///
+/// the native root certificates are trusted by default, the root certificates added by
+/// [`TlsConfig::with_root_certificate_pem`] are trusted too
+#[derive(Clone, Default)]
+pub struct TlsConfig {
+    root_certificates: Vec<Vec<u8>>,
+    identity: Option<(Vec<u8>, Vec<u8>)>,
+    disable_native_roots: bool,
+    #[cfg(any(feature = "tokio-rustls-tls", feature = "async-std-rustls-tls"))]
+    rustls_config: Option<Arc<ClientConfig>>,
+    #[cfg(any(feature = "tokio-native-tls", feature = "async-std-native-tls"))]
+    native_tls_connector: Option<native_tls::TlsConnector>,
+}
+
+impl Debug for TlsConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("TlsConfig");
+        debug
+            .field("root_certificates", &self.root_certificates.len())
+            .field("identity", &self.identity.is_some())
+            .field("disable_native_roots", &self.disable_native_roots);
+        #[cfg(any(feature = "tokio-rustls-tls", feature = "async-std-rustls-tls"))]
+        debug.field("rustls_config", &self.rustls_config.is_some());
+        #[cfg(any(feature = "tokio-native-tls", feature = "async-std-native-tls"))]
+        debug.field("native_tls_connector", &self.native_tls_connector.is_some());
+
+        debug.finish()
+    }
+}
+
+impl TlsConfig {
+    /// create the default tls config
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// trust the root certificates in the `pem`, the `pem` may contain multiple certificates
+    ///
+    /// building the client fails if the `pem` contains no certificate, such as the der bytes
+    pub fn with_root_certificate_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.root_certificates.push(pem.into());
+
+        self
+    }
+
+    /// set the client identity for the mutual tls, the `cert_chain` is the pem certificates
+    /// which starts with the client certificate, the `private_key` is the pem pkcs8 key
+    pub fn with_identity_pem(
+        mut self,
+        cert_chain: impl Into<Vec<u8>>,
+        private_key: impl Into<Vec<u8>>,
+    ) -> Self {
+        self.identity = Some((cert_chain.into(), private_key.into()));
+
+        self
+    }
+
+    /// don't trust the native root certificates, only the root certificates added by
+    /// [`TlsConfig::with_root_certificate_pem`] are trusted
+    pub fn without_native_roots(mut self) -> Self {
+        self.disable_native_roots = true;
+
+        self
+    }
+
+    /// use the rustls `config`, the other options of the [`TlsConfig`] are ignored
+    ///
+    /// the alpn protocols of the `config` are replaced by the ones decided by the
+    /// [`HttpVersion`](crate::client::HttpVersion)
+    #[cfg(any(feature = "tokio-rustls-tls", feature = "async-std-rustls-tls"))]
+    pub fn with_rustls_config(mut self, config: ClientConfig) -> Self {
+        self.rustls_config = Some(Arc::new(config));
+
+        self
+    }
+
+    /// use the native-tls `connector`, the other options of the [`TlsConfig`] are ignored
+    ///
+    /// the `connector` should request the alpn protocols for the http2, otherwise the http1.1 is
+    /// used
+    #[cfg(any(feature = "tokio-native-tls", feature = "async-std-native-tls"))]
+    pub fn with_native_tls_connector(mut self, connector: native_tls::TlsConnector) -> Self {
+        self.native_tls_connector = Some(connector);
+
+        self
+    }
+
+    /// build the rustls client config without the alpn protocols
+    #[cfg(any(feature = "tokio-rustls-tls", feature = "async-std-rustls-tls"))]
+    pub(crate) fn rustls_client_config(&self) -> Result<ClientConfig, Error> {
+        if let Some(config) = &self.rustls_config {
+            let mut config = (**config).clone();
+            config.alpn_protocols.clear();
+
+            return Ok(config);
+        }
+
+        let mut root_cert_store = RootCertStore::empty();
+        if !self.disable_native_roots {
+            let certs = rustls_native_certs::load_native_certs();
+            // some certs may fail to load, only fail when there is no cert
+            if let (true, Some(err)) = (certs.certs.is_empty(), certs.errors.first()) {
+                return Err(Error::Other(
+                    format!("load native certs failed: {err}").into(),
+                ));
+            }
+
+            let (added, ignored) = root_cert_store.add_parsable_certificates(certs.certs);
+
+            debug!(added, ignored, "load native certs done");
+        }
+
+        for pem in &self.root_certificates {
+            let certs = CertificateDer::pem_slice_iter(pem)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|err| Error::Other(format!("invalid root certificate: {err}").into()))?;
+            if certs.is_empty() {
+                return Err(no_pem_certificate());
+            }
+
+            for cert in certs {
+                root_cert_store.add(cert).map_err(|err| {
+                    Error::Other(format!("add root certificate failed: {err}").into())
+                })?;
+            }
+        }
+
+        let builder =
+            ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .map_err(|err| Error::Other(err.into()))?
+                .with_root_certificates(root_cert_store);
+
+        match &self.identity {
+            None => Ok(builder.with_no_client_auth()),
+
+            Some((cert_chain, private_key)) => {
+                let cert_chain = CertificateDer::pem_slice_iter(cert_chain)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|err| {
+                        Error::Other(format!("invalid client certificate: {err}").into())
+                    })?;
+                let private_key = PrivateKeyDer::from_pem_slice(private_key).map_err(|err| {
+                    Error::Other(format!("invalid client private key: {err}").into())
+                })?;
+
+                builder
+                    .with_client_auth_cert(cert_chain, private_key)
+                    .map_err(|err| Error::Other(err.into()))
+            }
+        }
+    }
+
+    /// build the native-tls connector which requests the `alpn_protocols`
+    #[cfg(any(feature = "tokio-native-tls", feature = "async-std-native-tls"))]
+    pub(crate) fn native_tls_connector(
+        &self,
+        alpn_protocols: &[&str],
+    ) -> Result<native_tls::TlsConnector, Error> {
+        if let Some(connector) = &self.native_tls_connector {
+            return Ok(connector.clone());
+        }
+
+        let mut builder = native_tls::TlsConnector::builder();
+        builder
+            .request_alpns(alpn_protocols)
+            .disable_built_in_roots(self.disable_native_roots);
+
+        for pem in &self.root_certificates {
+            let certs = native_tls::Certificate::stack_from_pem(pem)
+                .map_err(|err| Error::Other(format!("invalid root certificate: {err}").into()))?;
+            if certs.is_empty() {
+                return Err(no_pem_certificate());
+            }
+
+            for cert in certs {
+                builder.add_root_certificate(cert);
+            }
+        }
+
+        if let Some((cert_chain, private_key)) = &self.identity {
+            let identity = native_tls::Identity::from_pkcs8(cert_chain, private_key)
+                .map_err(|err| Error::Other(format!("invalid client identity: {err}").into()))?;
+            builder.identity(identity);
+        }
+
+        builder
+            .build()
+            .map_err(|err| Error::Other(format!("build tls connector failed: {err}").into()))
+    }
+}
+
+#[cfg(any(
+    feature = "tokio-rustls-tls",
+    feature = "tokio-native-tls",
+    feature = "async-std-rustls-tls",
+    feature = "async-std-native-tls"
+))]
+fn no_pem_certificate() -> Error {
+    Error::Other("invalid root certificate: no pem certificate found".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(any(
+        feature = "tokio-rustls-tls",
+        feature = "tokio-native-tls",
+        feature = "async-std-rustls-tls",
+        feature = "async-std-native-tls"
+    ))]
+    const CA_PEM: &str = "-----BEGIN CERTIFICATE-----
+MIIBlTCCATugAwIBAgIUe7ccsUnUcI2kxEdmfJfzOhmseREwCgYIKoZIzj0EAwIw
+HzEdMBsGA1UEAwwUdGVuY2VudGNsb3VkLXRlc3QtY2EwIBcNMjYxMDE3MTUxMzEw
+WhgPMjEyNjA5MjMxNTEzMTBaMB8xHTAbBgNVBAMMFHRlbmNlbnRjbG91ZC10ZXN0
+LWNhMFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEa1VniA7fipttdzRzHShyaLbC
+75gCiO9Ms9nYiBAtH5fAiitPGN59YygJEcizspMLr/5s6aveYsz6apOTLu/Z8KNT
+MFEwHQYDVR0OBBYEFIXX+UbkfAZQje3IbWBN+R99JZo/MB8GA1UdIwQYMBaAFIXX
++UbkfAZQje3IbWBN+R99JZo/MA8GA1UdEwEB/wQFMAMBAf8wCgYIKoZIzj0EAwID
+SAAwRQIhAM+kBqqTBrmQoIoyRtaK6EY5AkX86HzBSvb0iixqyFj+AiAig4jAUHTz
+w1zTLGfrIMXhZ54ZUNZi2j6nrC4nvdZyCg==
+-----END CERTIFICATE-----
+";
+
+    #[cfg(any(feature = "tokio-rustls-tls", feature = "async-std-rustls-tls"))]
+    #[test]
+    fn rustls_root_certificate() {
+        let config = TlsConfig::new()
+            .without_native_roots()
+            .with_root_certificate_pem(CA_PEM);
+        assert!(config.rustls_client_config().is_ok());
+
+        let config = TlsConfig::new()
+            .without_native_roots()
+            .with_root_certificate_pem(b"\x30\x82\x01\x95".to_vec());
+        assert!(config.rustls_client_config().is_err());
+
+        let config = TlsConfig::new()
+            .without_native_roots()
+            .with_root_certificate_pem("");
+        assert!(config.rustls_client_config().is_err());
+    }
+
+    #[cfg(any(feature = "tokio-native-tls", feature = "async-std-native-tls"))]
+    #[test]
+    fn native_tls_root_certificate() {
+        let config = TlsConfig::new().with_root_certificate_pem(CA_PEM);
+        assert!(config.native_tls_connector(&["h2"]).is_ok());
+
+        let config = TlsConfig::new().with_root_certificate_pem(b"\x30\x82\x01\x95".to_vec());
+        assert!(config.native_tls_connector(&["h2"]).is_err());
+    }
+
+    #[test]
+    fn debug_hides_identity() {
+        let config = TlsConfig::new().with_identity_pem("cert", "secret key");
+
+        assert!(!format!("{config:?}").contains("secret key"));
+    }
+}
@@ -1,17 +1,23 @@
 use std::future::{ready, Ready};
 use std::io;
-use std::io::ErrorKind;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 use futures_util::future::{BoxFuture, Either};
-use futures_util::{FutureExt, TryFutureExt};
-use hyper::client::connect::{Connected, Connection};
-use hyper::service::Service;
+use futures_util::FutureExt;
 use hyper::Uri;
+use hyper_util::client::legacy::connect::{Connected, Connection};
+use hyper_util::rt::TokioIo;
 use tokio::io::ReadBuf;
 use tokio::net::TcpStream;
 use tokio_native_tls::{TlsConnector, TlsStream};
+use tower_service::Service;
+
+use crate::error::Error;
+use crate::proxy::{tunnel, Proxy};
+use crate::timer;
+use crate::transport::TransportConfig;
 
 #[derive(Debug)]
 pub enum MaybeTls {
@@ -86,24 +92,24 @@ impl Connection for MaybeTls {
 #[derive(Debug, Clone)]
 pub struct Connector {
     tls_connector: TlsConnector,
+    connect_timeout: Option<Duration>,
+    proxy: Option<Proxy>,
 }
 
-impl Default for Connector {
-    fn default() -> Self {
-        let mut builder = native_tls::TlsConnector::builder();
-        let tls_connector = builder
-            .request_alpns(&["h2", "http/1.1"])
-            .build()
-            .unwrap_or_else(|err| panic!("build tls connector failed: {err}"));
+impl Connector {
+    pub fn new(config: &TransportConfig) -> Result<Self, Error> {
+        let tls_connector = config.tls.native_tls_connector(config.alpn_protocols())?;
 
-        Self {
+        Ok(Self {
             tls_connector: tls_connector.into(),
-        }
+            connect_timeout: config.connect_timeout,
+            proxy: config.proxy.clone(),
+        })
     }
 }
 
 impl Service<Uri> for Connector {
-    type Response = MaybeTls;
+    type Response = TokioIo<MaybeTls>;
     type Error = io::Error;
     type Future = Either<
         BoxFuture<'static, Result<Self::Response, Self::Error>>,
@@ -117,26 +123,32 @@ impl Service<Uri> for Connector {
     fn call(&mut self, req: Uri) -> Self::Future {
         let scheme = match req.scheme_str() {
             None => {
-                return ready(Err(io::Error::new(ErrorKind::Other, "miss scheme"))).right_future();
+                return ready(Err(io::Error::other("miss scheme"))).right_future();
             }
             Some(scheme) => scheme,
         };
         let host = match req.host() {
             None => {
-                return ready(Err(io::Error::new(ErrorKind::Other, "miss host"))).right_future();
+                return ready(Err(io::Error::other("miss host"))).right_future();
             }
             Some(host) => host,
         };
 
+        let proxy = self.proxy.clone();
+
         match scheme {
             "http" => {
                 let port = req.port_u16().unwrap_or(80);
                 let host = host.to_string();
+                let connect_timeout = self.connect_timeout;
 
                 async move {
-                    TcpStream::connect((host.as_str(), port))
-                        .map_ok(MaybeTls::Tcp)
-                        .await
+                    timer::maybe_timeout(connect_timeout, async {
+                        let tcp_stream = connect(&host, port, proxy.as_ref()).await?;
+
+                        Ok(TokioIo::new(MaybeTls::Tcp(tcp_stream)))
+                    })
+                    .await?
                 }
                 .boxed()
                 .left_future()
@@ -144,27 +156,51 @@ impl Service<Uri> for Connector {
 
             "https" => {
                 let port = req.port_u16().unwrap_or(443);
-                let host = host.to_string();
+                // the ipv6 host of the uri has the brackets, the tls domain is the ip
+                let host = host
+                    .trim_start_matches('[')
+                    .trim_end_matches(']')
+                    .to_string();
                 let tls_connector = self.tls_connector.clone();
+                let connect_timeout = self.connect_timeout;
 
+                // the timeout covers both tcp connect and tls handshake
                 async move {
-                    let tcp_stream = TcpStream::connect((host.as_str(), port)).await?;
-                    let tls_stream = tls_connector
-                        .connect(&host, tcp_stream)
-                        .await
-                        .map_err(|err| io::Error::new(ErrorKind::Other, err))?;
-
-                    Ok(MaybeTls::Tls(tls_stream))
+                    timer::maybe_timeout(connect_timeout, async {
+                        let tcp_stream = connect(&host, port, proxy.as_ref()).await?;
+                        let tls_stream = tls_connector
+                            .connect(&host, tcp_stream)
+                            .await
+                            .map_err(io::Error::other)?;
+
+                        Ok(TokioIo::new(MaybeTls::Tls(tls_stream)))
+                    })
+                    .await?
                 }
                 .boxed()
                 .left_future()
             }
 
-            scheme => ready(Err(io::Error::new(
-                ErrorKind::Other,
-                format!("invalid scheme: {scheme}"),
-            )))
-            .right_future(),
+            scheme => {
+                ready(Err(io::Error::other(format!("invalid scheme: {scheme}")))).right_future()
+            }
         }
     }
 }
+
+/// connect the `host`:`port` directly, or through the `proxy`
+async fn connect(host: &str, port: u16, proxy: Option<&Proxy>) -> io::Result<TcpStream> {
+    tunnel::connect(
+        proxy,
+        host,
+        port,
+        |host, port| async move { TcpStream::connect((host, port)).await },
+        |host, port| async move {
+            match tokio::net::lookup_host((host.as_str(), port)).await?.next() {
+                None => Err(io::Error::other(format!("{host} is not resolved"))),
+                Some(addr) => Ok(addr.ip()),
+            }
+        },
+    )
+    .await
+}
@@ -0,0 +1,319 @@
+//! tower integration
+//!
+//! the [`Client`] signs the request, then sends it by the [`Transport`], see the
+//! [`service`](crate::service) module for the layers of the pipeline; with the `tower` feature the
+//! transport can be built from the tower middlewares, so the application middlewares such as
+//! auditing, rate limiting or fault injection run between the signing and the sending:
+//!
+//! - [`TransportService`]: the [`Transport`] as the innermost tower service
+//! - [`ServiceTransport`]: the tower service as the [`Transport`]
+//! - [`ClientBuilder::transport_layer`]: wrap the transport of the client by the tower layer
+//! - [`ApiService`]: the [`Client`] as the tower service of an [`Api`]
+//!
+//! ## Examples:
+//!
+//! ```rust,no_run
+//! use std::task::{Context, Poll};
+//!
+//! use tencentcloud::tower::SignedRequest;
+//! use tencentcloud::{Auth, Client, Error};
+//! use tower::{Layer, Service, ServiceBuilder};
+//!
+//! #[derive(Debug, Clone)]
+//! struct Audit<S>(S);
+//!
+//! impl<S: Service<SignedRequest>> Service<SignedRequest> for Audit<S> {
+//!     type Response = S::Response;
+//!     type Error = S::Error;
+//!     type Future = S::Future;
+//!
+//!     fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+//!         self.0.poll_ready(cx)
+//!     }
+//!
+//!     fn call(&mut self, request: SignedRequest) -> Self::Future {
+//!         tracing::info!(action = ?request.headers().get("X-TC-Action"), "call api");
+//!
+//!         self.0.call(request)
+//!     }
+//! }
+//!
+//! # fn build() -> Result<(), Error> {
+//! let client = Client::builder()
+//!     .auth(Auth::new("key".into(), "id".into()))
+//!     .transport_layer(ServiceBuilder::new().layer_fn(Audit))
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use ::tower::{BoxError, Layer, Service};
+use futures_util::future::{poll_fn, BoxFuture};
+use futures_util::lock::Mutex;
+use futures_util::FutureExt;
+use hyper::Response;
+
+use crate::api::Api;
+use crate::client::{Client, ClientBuilder};
+use crate::error::Error;
+use crate::transport::{ResponseBody, Transport};
+
+pub use crate::service::{SignedRequest, TransportService};
+
+/// the tower service as the [`Transport`]
+///
+/// all the requests are sent by the same service, so the state such as the rate limit is shared
+/// by the requests; the requests wait for the readiness of the service in turn under an async
+/// lock, then the responses are awaited concurrently, so a service which stays pending delays
+/// all the requests, like the tower `Buffer` but without a spawned worker on a specific runtime
+#[derive(Debug, Clone)]
+pub struct ServiceTransport<S> {
+    service: Arc<Mutex<S>>,
+}
+
+impl<S> ServiceTransport<S> {
+    /// create the transport which sends the request by the `service`
+    pub fn new(service: S) -> Self {
+        Self {
+            service: Arc::new(Mutex::new(service)),
+        }
+    }
+}
+
+impl<S> Transport for ServiceTransport<S>
+where
+    S: Service<SignedRequest, Response = Response<ResponseBody>> + Debug + Send + 'static,
+    S::Error: Into<BoxError>,
+    S::Future: Send,
+{
+    fn send(&self, request: SignedRequest) -> BoxFuture<'_, Result<Response<ResponseBody>, Error>> {
+        async move {
+            let response = {
+                let mut service = self.service.lock().await;
+                poll_fn(|cx| service.poll_ready(cx))
+                    .await
+                    .map_err(into_error)?;
+
+                service.call(request)
+            };
+
+            response.await.map_err(into_error)
+        }
+        .boxed()
+    }
+}
+
+/// keep the [`Error`] returned by the inner [`TransportService`]
+fn into_error(err: impl Into<BoxError>) -> Error {
+    match err.into().downcast::<Error>() {
+        Ok(err) => *err,
+        Err(err) => Error::Other(err),
+    }
+}
+
+/// wrap the transport by a layer
+type WrapTransport = dyn FnOnce(Arc<dyn Transport>) -> Arc<dyn Transport> + Send + Sync;
+
+/// the deferred [`ClientBuilder::transport_layer`], it is applied when the client is built
+pub(crate) struct TransportLayer(Box<WrapTransport>);
+
+impl TransportLayer {
+    pub(crate) fn apply(self, transport: Arc<dyn Transport>) -> Arc<dyn Transport> {
+        (self.0)(transport)
+    }
+}
+
+impl Debug for TransportLayer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("TransportLayer")
+    }
+}
+
+impl ClientBuilder {
+    /// wrap the transport by the tower `layer`, the transport is the one set by
+    /// [`ClientBuilder::transport`] or the default transport
+    ///
+    /// the layers run after the request is signed, for every attempt of the retry; the layer
+    /// added first is the innermost one
+    pub fn transport_layer<L>(mut self, layer: L) -> Self
+    where
+        L: Layer<TransportService> + Send + Sync + 'static,
+        L::Service:
+            Service<SignedRequest, Response = Response<ResponseBody>> + Debug + Send + 'static,
+        <L::Service as Service<SignedRequest>>::Error: Into<BoxError>,
+        <L::Service as Service<SignedRequest>>::Future: Send,
+    {
+        self.transport_layers
+            .push(TransportLayer(Box::new(move |transport| {
+                Arc::new(ServiceTransport::new(
+                    layer.layer(TransportService::with_transport(transport)),
+                ))
+            })));
+
+        self
+    }
+}
+
+/// the [`Client`] as the tower service of the `A`: [`Api`], the response is the api response
+/// and the request id
+pub struct ApiService<A> {
+    client: Client,
+    _api: PhantomData<fn() -> A>,
+}
+
+impl<A> Debug for ApiService<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiService")
+            .field("client", &self.client)
+            .field("action", &std::any::type_name::<A>())
+            .finish()
+    }
+}
+
+impl<A> Clone for ApiService<A> {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            _api: PhantomData,
+        }
+    }
+}
+
+impl<A> Service<A::Request> for ApiService<A>
+where
+    A: Api + 'static,
+    A::Request: Send + Sync + 'static,
+    A::Response: Send + 'static,
+{
+    type Response = (A::Response, String);
+    type Error = Error;
+    type Future = BoxFuture<'static, Result<(A::Response, String), Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: A::Request) -> Self::Future {
+        let client = self.client.clone();
+
+        async move { client.send::<A>(&request).await }.boxed()
+    }
+}
+
+impl Client {
+    /// get the tower service of the `A`: [`Api`]
+    pub fn service<A: Api>(&self) -> ApiService<A> {
+        ApiService {
+            client: self.clone(),
+            _api: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::tower::ServiceBuilder;
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_util::{self, MockTransport, TestApi, TestRequest};
+
+    /// allow `remaining` requests, the state is not shared by the clones
+    #[derive(Debug, Clone)]
+    struct Quota<S> {
+        inner: S,
+        remaining: usize,
+    }
+
+    impl<S: Service<SignedRequest>> Service<SignedRequest> for Quota<S> {
+        type Response = S::Response;
+        type Error = BoxError;
+        type Future = futures_util::future::MapErr<S::Future, fn(S::Error) -> BoxError>;
+
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            if self.remaining == 0 {
+                return Poll::Ready(Err("quota exhausted".into()));
+            }
+
+            self.inner.poll_ready(cx).map_err(|_| "inner error".into())
+        }
+
+        fn call(&mut self, request: SignedRequest) -> Self::Future {
+            self.remaining -= 1;
+
+            futures_util::TryFutureExt::map_err(self.inner.call(request), |_| {
+                BoxError::from("inner error")
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn service_state_is_shared() {
+        let transport = MockTransport::new();
+        transport
+            .push_ok(json!({ "Status": "OK" }))
+            .push_ok(json!({ "Status": "OK" }));
+        let client = test_util::builder(transport.clone())
+            .transport_layer(ServiceBuilder::new().layer_fn(|inner| Quota {
+                inner,
+                remaining: 1,
+            }))
+            .build()
+            .unwrap();
+
+        client
+            .send::<TestApi>(&TestRequest::default())
+            .await
+            .unwrap();
+        let err = client
+            .send::<TestApi>(&TestRequest::default())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, Error::Other(err) if err.to_string() == "quota exhausted"));
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn concurrent_requests_share_service() {
+        let transport = MockTransport::new();
+        for _ in 0..3 {
+            transport.push_ok(json!({ "Status": "OK" }));
+        }
+        let client = test_util::builder(transport.clone())
+            .transport_layer(ServiceBuilder::new().layer_fn(|inner| Quota {
+                inner,
+                remaining: 3,
+            }))
+            .build()
+            .unwrap();
+
+        let request = TestRequest::default();
+        let results =
+            futures_util::future::join_all((0..5).map(|_| client.send::<TestApi>(&request))).await;
+
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 3);
+        assert_eq!(transport.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn api_service() {
+        let transport = MockTransport::new();
+        transport.push_ok(json!({ "Status": "OK" }));
+        let mut service = test_util::builder(transport)
+            .build()
+            .unwrap()
+            .service::<TestApi>();
+
+        poll_fn(|cx| service.poll_ready(cx)).await.unwrap();
+        let (resp, request_id) = service.call(TestRequest::default()).await.unwrap();
+
+        assert_eq!(resp.status.as_deref(), Some("OK"));
+        assert_eq!(request_id, "request-id");
+    }
+}
@@ -0,0 +1,259 @@
+//! http transports
+//!
+//! the [`Client`](crate::Client) sends the signed http request by the [`Transport`], use
+//! [`ClientBuilder::transport`](crate::ClientBuilder::transport) to share the http stack of the
+//! application, for example a tuned hyper client or a tower service
+//!
+//! every tls feature provides a built-in transport, the hyper client with the connector of the
+//! tls backend, the features are additive:
+//!
+//! - `tokio-rustls-tls`: `TokioRustlsTransport`
+//! - `tokio-native-tls`: `TokioNativeTlsTransport`
+//! - `async-std-rustls-tls`: `AsyncStdRustlsTransport`
+//! - `async-std-native-tls`: `AsyncStdNativeTlsTransport`
+//!
+//! the `DefaultTransport` is the first enabled one in the above order; without any tls
+//! feature, the transport must be set by the
+//! [`ClientBuilder::transport`](crate::ClientBuilder::transport)
+//!
+//! ## Examples:
+//!
+//! ```rust,no_run
+//! use futures_util::future::BoxFuture;
+//! use hyper::{Request, Response};
+//! use tencentcloud::proxy::Proxy;
+//! use tencentcloud::transport::{RequestBody, ResponseBody, Transport, TransportConfig};
+//! use tencentcloud::{Auth, Client, Error};
+//!
+//! #[derive(Debug)]
+//! struct Logging<T>(T);
+//!
+//! impl<T: Transport> Transport for Logging<T> {
+//!     fn send(
+//!         &self,
+//!         request: Request<RequestBody>,
+//!     ) -> BoxFuture<'_, Result<Response<ResponseBody>, Error>> {
+//!         println!("{} {}", request.method(), request.uri());
+//!
+//!         self.0.send(request)
+//!     }
+//! }
+//!
+//! # #[cfg(feature = "tokio-rustls-tls")]
+//! # fn build() -> Result<(), Error> {
+//! use tencentcloud::transport::DefaultTransport;
+//!
+//! let config = TransportConfig::new().with_proxy(Proxy::from_env());
+//! let client = Client::builder()
+//!     .auth(Auth::new("key".into(), "id".into()))
+//!     .transport(Logging(DefaultTransport::new(&config)?))
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::error;
+use std::fmt::Debug;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use futures_util::FutureExt;
+use http_body_util::combinators::BoxBody;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Bytes;
+use hyper::{Request, Response};
+use hyper_util::client::legacy::connect::Connect;
+use hyper_util::client::legacy::Client;
+
+use crate::client::HttpVersion;
+use crate::error::Error;
+#[cfg(feature = "async-std-native-tls")]
+pub use crate::http_client::AsyncStdNativeTlsTransport;
+#[cfg(feature = "async-std-rustls-tls")]
+pub use crate::http_client::AsyncStdRustlsTransport;
+#[cfg(any(
+    feature = "tokio-rustls-tls",
+    feature = "tokio-native-tls",
+    feature = "async-std-rustls-tls",
+    feature = "async-std-native-tls"
+))]
+pub use crate::http_client::DefaultTransport;
+#[cfg(feature = "tokio-native-tls")]
+pub use crate::http_client::TokioNativeTlsTransport;
+#[cfg(feature = "tokio-rustls-tls")]
+pub use crate::http_client::TokioRustlsTransport;
+use crate::proxy::Proxy;
+use crate::tls::TlsConfig;
+
+/// the body of the http request
+pub type RequestBody = Full<Bytes>;
+
+/// the body of the http response
+pub type ResponseBody = BoxBody<Bytes, Box<dyn error::Error + Send + Sync + 'static>>;
+
+/// send the http request and get the http response
+///
+/// the transport only sends the request, the retry, timeout and response size limit are handled
+/// by the [`Client`](crate::Client); return [`Error::Connect`] if the connection can't be
+/// established, or it is closed before the response is received, so the request is retried
+pub trait Transport: Debug + Send + Sync {
+    /// send the `request`
+    fn send(
+        &self,
+        request: Request<RequestBody>,
+    ) -> BoxFuture<'_, Result<Response<ResponseBody>, Error>>;
+}
+
+impl<C> Transport for Client<C, RequestBody>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    fn send(
+        &self,
+        request: Request<RequestBody>,
+    ) -> BoxFuture<'_, Result<Response<ResponseBody>, Error>> {
+        let response = self.request(request);
+
+        async move {
+            match response.await {
+                Ok(response) => Ok(response.map(|body| body.map_err(box_error).boxed())),
+                Err(err) if err.is_connect() || is_connection_closed(&err) => {
+                    Err(Error::Connect(err.into()))
+                }
+                Err(err) => Err(Error::Other(err.into())),
+            }
+        }
+        .boxed()
+    }
+}
+
+/// whether the connection is closed or reset before the response is received, for example the
+/// pooled connection is closed by the server
+fn is_connection_closed(err: &hyper_util::client::legacy::Error) -> bool {
+    let mut source = error::Error::source(err);
+    while let Some(err) = source {
+        if let Some(err) = err.downcast_ref::<hyper::Error>() {
+            if err.is_incomplete_message() || err.is_closed() || err.is_canceled() {
+                return true;
+            }
+        }
+
+        if let Some(err) = err.downcast_ref::<io::Error>() {
+            if matches!(
+                err.kind(),
+                io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::BrokenPipe
+                    | io::ErrorKind::UnexpectedEof
+            ) {
+                return true;
+            }
+        }
+
+        source = err.source();
+    }
+
+    false
+}
+
+impl<T: Transport + ?Sized> Transport for Arc<T> {
+    fn send(
+        &self,
+        request: Request<RequestBody>,
+    ) -> BoxFuture<'_, Result<Response<ResponseBody>, Error>> {
+        (**self).send(request)
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    fn send(
+        &self,
+        request: Request<RequestBody>,
+    ) -> BoxFuture<'_, Result<Response<ResponseBody>, Error>> {
+        (**self).send(request)
+    }
+}
+
+fn box_error(err: hyper::Error) -> Box<dyn error::Error + Send + Sync + 'static> {
+    Box::new(err)
+}
+
+/// convert the error of reading the [`ResponseBody`]
+pub(crate) fn body_error(err: Box<dyn error::Error + Send + Sync + 'static>) -> Error {
+    match err.downcast::<hyper::Error>() {
+        Ok(err) => Error::Http(*err),
+        Err(err) => Error::Other(err),
+    }
+}
+
+/// the options of the built-in transports
+#[derive(Debug, Clone, Default)]
+pub struct TransportConfig {
+    pub(crate) connect_timeout: Option<Duration>,
+    pub(crate) http_version: HttpVersion,
+    pub(crate) proxy: Option<Proxy>,
+    pub(crate) tls: TlsConfig,
+}
+
+impl TransportConfig {
+    /// create the config, connect directly without the connect timeout
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// set the timeout of connecting, it covers the tcp connect, the proxy handshake and the tls
+    /// handshake
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+
+        self
+    }
+
+    /// set the http version preference, default is [`HttpVersion::Auto`]
+    pub fn with_http_version(mut self, http_version: HttpVersion) -> Self {
+        self.http_version = http_version;
+
+        self
+    }
+
+    /// connect through the `proxy`, it is not read from the environment variables, see
+    /// [`Proxy::from_env`]
+    pub fn with_proxy(mut self, proxy: impl Into<Option<Proxy>>) -> Self {
+        self.proxy = proxy.into();
+
+        self
+    }
+
+    /// set the [`TlsConfig`]
+    pub fn with_tls_config(mut self, tls_config: TlsConfig) -> Self {
+        self.tls = tls_config;
+
+        self
+    }
+
+    /// the tls alpn protocols which should be requested
+    #[cfg(any(
+        feature = "tokio-native-tls",
+        feature = "async-std-rustls-tls",
+        feature = "async-std-native-tls"
+    ))]
+    pub(crate) fn alpn_protocols(&self) -> &'static [&'static str] {
+        match self.http_version {
+            HttpVersion::Auto => &["h2", "http/1.1"],
+            HttpVersion::Http1Only => &["http/1.1"],
+            HttpVersion::Http2Only => &["h2"],
+        }
+    }
+
+    #[cfg(any(
+        feature = "tokio-rustls-tls",
+        feature = "tokio-native-tls",
+        feature = "async-std-rustls-tls",
+        feature = "async-std-native-tls"
+    ))]
+    pub(crate) fn http2_only(&self) -> bool {
+        self.http_version == HttpVersion::Http2Only
+    }
+}
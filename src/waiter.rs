@@ -0,0 +1,457 @@
+//! waiters which poll the describe api until the resource reaches the target state
+//!
+//! the [`Waiter`] decides the polling result by its matchers in order, the first matched
+//! [`WaitState`] is used; if no matcher matches, the api error is returned and the response is
+//! polled again
+//!
+//! [`Client::wait`] returns the response which reaches the target state, or `None` if the target
+//! state is reached by an error, for example: waiting the resource is deleted by
+//! [`error_code`] with [`WaitState::Success`]
+//!
+//! ## Examples:
+//!
+//! ```rust,no_run
+//! # use serde::{Deserialize, Serialize};
+//! # use tencentcloud::api::Api;
+//! use tencentcloud::waiter::{self, Waiter};
+//! # use tencentcloud::{Auth, Client};
+//! #
+//! # #[derive(Debug, Copy, Clone)]
+//! # pub struct DescribeInstances;
+//! #
+//! # #[derive(Debug, Clone, Default, Serialize)]
+//! # pub struct DescribeInstancesRequest {
+//! #     #[serde(rename = "InstanceIds")]
+//! #     pub instance_ids: Vec<String>,
+//! # }
+//! #
+//! # #[derive(Debug, Clone, Deserialize)]
+//! # pub struct DescribeInstancesResponse {
+//! #     #[serde(rename = "InstanceSet")]
+//! #     pub instance_set: Vec<Instance>,
+//! # }
+//! #
+//! # #[derive(Debug, Clone, Deserialize)]
+//! # pub struct Instance {
+//! #     #[serde(rename = "InstanceState")]
+//! #     pub instance_state: String,
+//! # }
+//! #
+//! # impl Api for DescribeInstances {
+//! #     type Request = DescribeInstancesRequest;
+//! #     type Response = DescribeInstancesResponse;
+//! #     const VERSION: &'static str = "2017-03-12";
+//! #     const ACTION: &'static str = "DescribeInstances";
+//! #     const SERVICE: &'static str = "cvm";
+//! #     const HOST: &'static str = "cvm.tencentcloudapi.com";
+//! # }
+//! #
+//! # async fn wait(client: Client) -> Result<(), tencentcloud::Error> {
+//! let waiter = Waiter::new().with_matcher(waiter::all_status(
+//!     |resp: &DescribeInstancesResponse| {
+//!         resp.instance_set
+//!             .iter()
+//!             .map(|instance| instance.instance_state.clone())
+//!             .collect()
+//!     },
+//!     ["RUNNING"],
+//!     ["LAUNCH_FAILED"],
+//! ));
+//!
+//! let request = DescribeInstancesRequest {
+//!     instance_ids: vec!["ins-xxx".to_string()],
+//! };
+//! let response = client
+//!     .wait::<DescribeInstances>(&request, &waiter)
+//!     .await?
+//!     .expect("the status matcher only matches the response");
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt::{Debug, Formatter};
+use std::time::Duration;
+
+use tracing::{debug, instrument};
+
+use crate::api::Api;
+use crate::client::Client;
+use crate::error::Error;
+use crate::timer;
+
+/// the default min delay between the polls
+const DEFAULT_MIN_DELAY: Duration = Duration::from_secs(2);
+
+/// the default max delay between the polls
+const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// the default timeout of the waiting
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// the result of a matcher
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum WaitState {
+    /// the resource reaches the target state
+    Success,
+
+    /// the resource reaches the state which never changes to the target state
+    Failure(String),
+
+    /// poll again
+    Retry,
+}
+
+/// the matcher of the polling result, `None` means not matched
+pub type Matcher<R> = Box<dyn Fn(Result<&R, &Error>) -> Option<WaitState> + Send + Sync>;
+
+/// poll the describe api until the resource reaches the target state
+///
+/// the delay between the polls grows exponentially from the min delay to the max delay with
+/// jitter, the default is 2s to 30s, and the default timeout is 10 minutes
+pub struct Waiter<R> {
+    matchers: Vec<Matcher<R>>,
+    min_delay: Duration,
+    max_delay: Duration,
+    timeout: Duration,
+}
+
+impl<R> Debug for Waiter<R> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Waiter")
+            .field("matchers", &self.matchers.len())
+            .field("min_delay", &self.min_delay)
+            .field("max_delay", &self.max_delay)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl<R> Default for Waiter<R> {
+    fn default() -> Self {
+        Self {
+            matchers: vec![],
+            min_delay: DEFAULT_MIN_DELAY,
+            max_delay: DEFAULT_MAX_DELAY,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+impl<R> Waiter<R> {
+    /// create the waiter without matcher, at least one matcher must be added before waiting
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// add the matcher, the matchers are evaluated in order
+    pub fn with_matcher(
+        mut self,
+        matcher: impl Fn(Result<&R, &Error>) -> Option<WaitState> + Send + Sync + 'static,
+    ) -> Self {
+        self.matchers.push(Box::new(matcher));
+
+        self
+    }
+
+    /// set the min and max delay between the polls
+    pub fn with_delay(mut self, min_delay: Duration, max_delay: Duration) -> Self {
+        self.min_delay = min_delay;
+        self.max_delay = max_delay.max(min_delay);
+
+        self
+    }
+
+    /// set the timeout of the waiting
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+
+        self
+    }
+
+    fn evaluate(&self, result: Result<&R, &Error>) -> Option<WaitState> {
+        self.matchers.iter().find_map(|matcher| matcher(result))
+    }
+
+    fn delay(&self, attempt: u32) -> Duration {
+        let max_delay = self
+            .min_delay
+            .saturating_mul(1 << attempt.min(16))
+            .min(self.max_delay);
+
+        self.min_delay + (max_delay - self.min_delay).mul_f64(fastrand::f64())
+    }
+}
+
+impl Client {
+    /// send the `A`: [`Api`] repeatedly until the [`Waiter`] decides, get the last response
+    ///
+    /// the response is `None` if the target state is reached by an api error, such as the
+    /// `ResourceNotFound` when waiting the resource is deleted
+    ///
+    /// [`Error::WaitFailure`] is returned when the failure state is reached, [`Error::Timeout`]
+    /// is returned when the waiting is timeout; the waiter without matcher is rejected without
+    /// sending the request, because it never reaches any state
+    #[instrument(level = "trace", skip(waiter), err)]
+    pub async fn wait<A: Api>(
+        &self,
+        request: &A::Request,
+        waiter: &Waiter<A::Response>,
+    ) -> Result<Option<A::Response>, Error> {
+        if waiter.matchers.is_empty() {
+            return Err(Error::Other("the waiter has no matcher".into()));
+        }
+
+        timer::timeout(waiter.timeout, async {
+            let mut attempt = 0;
+            loop {
+                let result = self.send::<A>(request).await.map(|(resp, _)| resp);
+
+                match waiter.evaluate(result.as_ref()) {
+                    Some(WaitState::Success) => return Ok(result.ok()),
+                    Some(WaitState::Failure(reason)) => return Err(Error::WaitFailure(reason)),
+                    Some(WaitState::Retry) => {}
+                    // the error which no matcher expects
+                    None => {
+                        result?;
+                    }
+                }
+
+                let delay = waiter.delay(attempt);
+                attempt += 1;
+
+                debug!(
+                    attempt,
+                    ?delay,
+                    "resource doesn't reach the state, poll again"
+                );
+
+                timer::sleep(delay).await;
+            }
+        })
+        .await
+        .map_err(|_| Error::Timeout)?
+    }
+}
+
+/// match the status of the response, `status` gets the status from the response
+///
+/// matched with [`WaitState::Success`] if the status is one of the `success`, with
+/// [`WaitState::Failure`] if it is one of the `failure`, otherwise with [`WaitState::Retry`]
+pub fn status<R, F>(
+    status: F,
+    success: impl IntoIterator<Item = impl Into<String>>,
+    failure: impl IntoIterator<Item = impl Into<String>>,
+) -> impl Fn(Result<&R, &Error>) -> Option<WaitState> + Send + Sync + 'static
+where
+    F: Fn(&R) -> Option<String> + Send + Sync + 'static,
+{
+    let success = success.into_iter().map(Into::into).collect::<Vec<String>>();
+    let failure = failure.into_iter().map(Into::into).collect::<Vec<String>>();
+
+    move |result| {
+        let status = status(result.ok()?);
+
+        Some(match status {
+            Some(status) if success.contains(&status) => WaitState::Success,
+            Some(status) if failure.contains(&status) => {
+                WaitState::Failure(format!("status is {status}"))
+            }
+            _ => WaitState::Retry,
+        })
+    }
+}
+
+/// match the statuses of the resources in the response, `statuses` gets the statuses from the
+/// response
+///
+/// matched with [`WaitState::Success`] if there are statuses and all of them are one of the
+/// `success`, with [`WaitState::Failure`] if any of them is one of the `failure`, otherwise with
+/// [`WaitState::Retry`]
+pub fn all_status<R, F>(
+    statuses: F,
+    success: impl IntoIterator<Item = impl Into<String>>,
+    failure: impl IntoIterator<Item = impl Into<String>>,
+) -> impl Fn(Result<&R, &Error>) -> Option<WaitState> + Send + Sync + 'static
+where
+    F: Fn(&R) -> Vec<String> + Send + Sync + 'static,
+{
+    let success = success.into_iter().map(Into::into).collect::<Vec<String>>();
+    let failure = failure.into_iter().map(Into::into).collect::<Vec<String>>();
+
+    move |result| {
+        let statuses = statuses(result.ok()?);
+
+        Some(
+            if let Some(status) = statuses.iter().find(|status| failure.contains(status)) {
+                WaitState::Failure(format!("status is {status}"))
+            } else if !statuses.is_empty() && statuses.iter().all(|status| success.contains(status))
+            {
+                WaitState::Success
+            } else {
+                WaitState::Retry
+            },
+        )
+    }
+}
+
+/// match the api error code, the `code` matches the error code itself and its subcodes, for
+/// example: `ResourceNotFound` matches `ResourceNotFound.InstanceNotFound`
+///
+/// it is useful to wait the resource is deleted with [`WaitState::Success`], or to poll again
+/// when the created resource is not visible yet with [`WaitState::Retry`]
+pub fn error_code<R>(
+    code: impl Into<String>,
+    state: WaitState,
+) -> impl Fn(Result<&R, &Error>) -> Option<WaitState> + Send + Sync + 'static {
+    let code = code.into();
+
+    move |result| {
+        let matched = result.err().and_then(Error::code).is_some_and(|err_code| {
+            let err_code = err_code.as_str();
+
+            err_code == code
+                || err_code
+                    .strip_prefix(code.as_str())
+                    .is_some_and(|subcode| subcode.starts_with('.'))
+        });
+
+        matched.then(|| state.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_util::{self, MockTransport, TestApi, TestRequest, TestResponse};
+
+    fn waiter() -> Waiter<TestResponse> {
+        Waiter::new()
+            .with_delay(Duration::from_millis(1), Duration::from_millis(1))
+            .with_timeout(Duration::from_secs(5))
+    }
+
+    fn status_of(resp: &TestResponse) -> Option<String> {
+        resp.status.clone()
+    }
+
+    #[tokio::test]
+    async fn wait_status_success() {
+        let transport = MockTransport::new();
+        transport
+            .push_ok(json!({ "Status": "PENDING" }))
+            .push_ok(json!({ "Status": "RUNNING" }));
+        let client = test_util::builder(transport.clone()).build().unwrap();
+        let waiter = waiter().with_matcher(status(status_of, ["RUNNING"], ["FAILED"]));
+
+        let resp = client
+            .wait::<TestApi>(&TestRequest::default(), &waiter)
+            .await
+            .unwrap();
+
+        assert_eq!(resp.unwrap().status.as_deref(), Some("RUNNING"));
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].headers["x-tc-action"], "TestAction");
+        assert_eq!(requests[1].body, json!({}));
+    }
+
+    #[tokio::test]
+    async fn wait_without_matcher() {
+        let transport = MockTransport::new();
+        let client = test_util::builder(transport.clone()).build().unwrap();
+
+        let err = client
+            .wait::<TestApi>(&TestRequest::default(), &waiter())
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("no matcher"), "{err}");
+        assert!(transport.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn wait_deleted_by_error_code() {
+        let transport = MockTransport::new();
+        transport
+            .push_ok(json!({ "Status": "SHUTDOWN" }))
+            .push_api_error("ResourceNotFound.InstanceNotFound");
+        let client = test_util::builder(transport.clone()).build().unwrap();
+        let waiter = waiter()
+            .with_matcher(error_code("ResourceNotFound", WaitState::Success))
+            .with_matcher(status(status_of, [] as [&str; 0], ["FAILED"]));
+
+        let resp = client
+            .wait::<TestApi>(&TestRequest::default(), &waiter)
+            .await
+            .unwrap();
+
+        assert!(resp.is_none());
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn wait_failure() {
+        let transport = MockTransport::new();
+        transport.push_ok(json!({ "Status": "FAILED" }));
+        let client = test_util::builder(transport).build().unwrap();
+        let waiter = waiter().with_matcher(status(status_of, ["RUNNING"], ["FAILED"]));
+
+        let err = client
+            .wait::<TestApi>(&TestRequest::default(), &waiter)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, Error::WaitFailure(reason) if reason == "status is FAILED"));
+    }
+
+    #[tokio::test]
+    async fn wait_unexpected_error() {
+        let transport = MockTransport::new();
+        transport.push_api_error("AuthFailure.SecretIdNotFound");
+        let client = test_util::builder(transport).build().unwrap();
+        let waiter = waiter()
+            .with_matcher(error_code("ResourceNotFound", WaitState::Success))
+            .with_matcher(status(status_of, ["RUNNING"], ["FAILED"]));
+
+        let err = client
+            .wait::<TestApi>(&TestRequest::default(), &waiter)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.code().map(|code| code.as_str().to_string()).as_deref(),
+            Some("AuthFailure.SecretIdNotFound")
+        );
+    }
+
+    #[test]
+    fn error_code_matches_subcode() {
+        let matcher = error_code::<TestResponse>("ResourceNotFound", WaitState::Success);
+        let api_error = |code: &str| {
+            serde_json::from_value::<crate::error::ApiError>(json!({
+                "Code": code,
+                "Message": "m",
+            }))
+            .map(|err| Error::Api {
+                err,
+                request_id: "r".to_string(),
+            })
+            .unwrap()
+        };
+
+        assert_eq!(
+            matcher(Err(&api_error("ResourceNotFound"))),
+            Some(WaitState::Success)
+        );
+        assert_eq!(
+            matcher(Err(&api_error("ResourceNotFound.InstanceNotFound"))),
+            Some(WaitState::Success)
+        );
+        assert_eq!(matcher(Err(&api_error("ResourceNotFoundX"))), None);
+        assert_eq!(matcher(Err(&api_error("InternalError"))), None);
+    }
+}
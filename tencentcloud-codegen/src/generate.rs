@@ -0,0 +1,357 @@
+//! render the service version module from the [`Spec`]
+
+use std::collections::{BTreeSet, HashSet};
+use std::fmt::Write;
+
+use crate::spec::{Member, Object, Spec};
+
+/// the rust keywords which can't be the field name
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "do", "dyn", "else", "enum",
+    "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "macro",
+    "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "static", "struct",
+    "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual", "where",
+    "while", "yield",
+];
+
+/// the keywords which can't be the raw identifier
+const NON_RAW_KEYWORDS: &[&str] = &["crate", "self", "super", "Self"];
+
+/// the role of the object decides its derives and how the optional members are typed
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Role {
+    Request,
+    Response,
+    Model,
+}
+
+/// the module name of the service
+pub fn service_module(service: &str) -> String {
+    service.replace('-', "_").to_ascii_lowercase()
+}
+
+/// the module name of the api version, for example: `2017-03-12` is `v20170312`
+pub fn version_module(version: &str) -> String {
+    format!(
+        "v{}",
+        version
+            .chars()
+            .filter(char::is_ascii_digit)
+            .collect::<String>()
+    )
+}
+
+/// render the service version module
+pub fn render(spec: &Spec) -> String {
+    let service = &spec.metadata.service_short_name;
+    let version = &spec.metadata.api_version;
+
+    let requests = spec
+        .actions
+        .values()
+        .map(|action| action.input.as_str())
+        .collect::<HashSet<_>>();
+    let responses = spec
+        .actions
+        .values()
+        .map(|action| action.output.as_str())
+        .collect::<HashSet<_>>();
+
+    let mut out = String::new();
+    let title = match &spec.metadata.service_name_cn {
+        None => format!("{service} {version}"),
+        Some(name) => format!("{name} {service} {version}"),
+    };
+    writeln!(out, "//! {title}").unwrap();
+    writeln!(out, "//!").unwrap();
+    writeln!(
+        out,
+        "//! generated by the tencentcloud-codegen from the api spec, don't edit it by hand"
+    )
+    .unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "#![allow(clippy::all, rustdoc::all)]").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "use crate::api::Api;").unwrap();
+
+    for (name, action) in &spec.actions {
+        let idempotent = spec.objects.get(&action.input).is_some_and(|input| {
+            input
+                .members
+                .iter()
+                .any(|member| member.name == "ClientToken")
+        });
+
+        writeln!(out).unwrap();
+        write_doc(&mut out, "", &action.document);
+        if action.status.as_deref() == Some("deprecated") {
+            writeln!(out, "#[deprecated]").unwrap();
+        }
+        writeln!(out, "#[derive(Debug, Copy, Clone)]").unwrap();
+        writeln!(out, "pub struct {};", type_ident(name)).unwrap();
+        writeln!(out).unwrap();
+        if action.status.as_deref() == Some("deprecated") {
+            writeln!(out, "#[allow(deprecated)]").unwrap();
+        }
+        writeln!(out, "impl Api for {} {{", type_ident(name)).unwrap();
+        writeln!(out, "    type Request = {};", type_ident(&action.input)).unwrap();
+        writeln!(out, "    type Response = {};", type_ident(&action.output)).unwrap();
+        writeln!(out, "    const VERSION: &'static str = {version:?};").unwrap();
+        writeln!(out, "    const ACTION: &'static str = {name:?};").unwrap();
+        writeln!(out, "    const SERVICE: &'static str = {service:?};").unwrap();
+        writeln!(
+            out,
+            "    const HOST: &'static str = \"{service}.tencentcloudapi.com\";"
+        )
+        .unwrap();
+        if idempotent {
+            writeln!(
+                out,
+                "    const IDEMPOTENCY_TOKEN_FIELD: Option<&'static str> = Some(\"ClientToken\");"
+            )
+            .unwrap();
+        }
+        writeln!(out, "}}").unwrap();
+    }
+
+    for (name, object) in &spec.objects {
+        let role = if requests.contains(name.as_str()) {
+            Role::Request
+        } else if responses.contains(name.as_str()) {
+            Role::Response
+        } else {
+            Role::Model
+        };
+
+        writeln!(out).unwrap();
+        render_object(&mut out, name, object, role);
+    }
+
+    out
+}
+
+fn render_object(out: &mut String, name: &str, object: &Object, role: Role) {
+    let derives = match role {
+        Role::Request => "Debug, Clone, Default, serde::Serialize",
+        Role::Response => "Debug, Clone, serde::Deserialize",
+        Role::Model => "Debug, Clone, Default, serde::Serialize, serde::Deserialize",
+    };
+
+    write_doc(out, "", &object.document);
+    writeln!(out, "#[derive({derives})]").unwrap();
+    writeln!(out, "pub struct {} {{", type_ident(name)).unwrap();
+
+    let mut fields = BTreeSet::new();
+    let mut first = true;
+    for member in &object.members {
+        // the request id is returned by the Client
+        if role == Role::Response && member.name == "RequestId" {
+            continue;
+        }
+
+        if !first {
+            writeln!(out).unwrap();
+        }
+        first = false;
+
+        let mut field = field_ident(&member.name);
+        while !fields.insert(field.clone()) {
+            field.push('_');
+        }
+
+        let optional = match role {
+            Role::Request => !member.required,
+            Role::Response => true,
+            Role::Model => !(member.required && object.usage.as_deref() == Some("in")),
+        };
+        let mut ty = member_type(member);
+        if member.ty == "object" && member.member == name {
+            ty = format!("Box<{ty}>");
+        }
+
+        write_doc(out, "    ", &member.document);
+        if role == Role::Response {
+            writeln!(out, "    #[serde(rename = {:?})]", member.name).unwrap();
+            writeln!(out, "    pub {field}: Option<{ty}>,").unwrap();
+        } else if optional {
+            writeln!(
+                out,
+                "    #[serde(rename = {:?}, skip_serializing_if = \"Option::is_none\")]",
+                member.name
+            )
+            .unwrap();
+            writeln!(out, "    pub {field}: Option<{ty}>,").unwrap();
+        } else {
+            writeln!(out, "    #[serde(rename = {:?})]", member.name).unwrap();
+            writeln!(out, "    pub {field}: {ty},").unwrap();
+        }
+    }
+
+    writeln!(out, "}}").unwrap();
+}
+
+/// the rust type of the `member`
+fn member_type(member: &Member) -> String {
+    match member.ty.as_str() {
+        "list" => format!("Vec<{}>", element_type(&member.member)),
+        "object" => type_ident(&member.member),
+        ty => scalar_type(ty).unwrap_or("serde_json::Value").to_string(),
+    }
+}
+
+/// the element type of the list, it is a scalar type or an object name
+fn element_type(name: &str) -> String {
+    match scalar_type(name) {
+        Some(ty) => ty.to_string(),
+        None if name.chars().next().is_some_and(char::is_uppercase) => type_ident(name),
+        None => "serde_json::Value".to_string(),
+    }
+}
+
+fn scalar_type(ty: &str) -> Option<&'static str> {
+    let ty = match ty {
+        "string" | "binary" | "date" | "datetime" | "datetime_iso" | "time" => "String",
+        "int" | "integer" | "int64" => "i64",
+        "uint" | "uint64" => "u64",
+        "float" | "double" => "f64",
+        "bool" | "boolean" => "bool",
+        "json" | "any" => "serde_json::Value",
+        _ => return None,
+    };
+
+    Some(ty)
+}
+
+/// write the `document` as the doc comment, the code blocks are marked as `text` so they are not
+/// run as the doc tests
+fn write_doc(out: &mut String, indent: &str, document: &str) {
+    let mut in_code = false;
+    for line in document.lines().map(str::trim) {
+        if line.starts_with("```") {
+            if in_code {
+                writeln!(out, "{indent}/// ```").unwrap();
+            } else {
+                writeln!(out, "{indent}/// ```text").unwrap();
+            }
+            in_code = !in_code;
+        } else if line.is_empty() {
+            writeln!(out, "{indent}///").unwrap();
+        } else {
+            writeln!(out, "{indent}/// {line}").unwrap();
+        }
+    }
+    if in_code {
+        writeln!(out, "{indent}/// ```").unwrap();
+    }
+}
+
+fn type_ident(name: &str) -> String {
+    let name = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .collect::<String>();
+
+    ident(&name)
+}
+
+/// convert the PascalCase member name to the snake_case field name, for example: `VPCId` is
+/// `vpc_id`
+fn field_ident(name: &str) -> String {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut field = String::with_capacity(name.len() + 4);
+
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() {
+            let prev = i.checked_sub(1).map(|i| chars[i]);
+            let next = chars.get(i + 1);
+            let boundary = match prev {
+                None => false,
+                Some(prev) => {
+                    prev.is_ascii_lowercase()
+                        || prev.is_ascii_digit()
+                        || (prev.is_ascii_uppercase() && next.is_some_and(char::is_ascii_lowercase))
+                }
+            };
+            if boundary && !field.ends_with('_') {
+                field.push('_');
+            }
+
+            field.push(c.to_ascii_lowercase());
+        } else if c.is_ascii_alphanumeric() {
+            field.push(c);
+        } else if !field.is_empty() && !field.ends_with('_') {
+            field.push('_');
+        }
+    }
+
+    ident(field.trim_end_matches('_'))
+}
+
+/// escape the `name` if it is a keyword or starts with a digit
+fn ident(name: &str) -> String {
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{name}")
+    } else if NON_RAW_KEYWORDS.contains(&name) {
+        format!("{name}_")
+    } else if KEYWORDS.contains(&name) {
+        format!("r#{name}")
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> Spec {
+        serde_json::from_str(include_str!("../tests/fixtures/demo/api.json")).unwrap()
+    }
+
+    #[test]
+    fn field_idents() {
+        let cases = [
+            ("InstanceId", "instance_id"),
+            ("VPCId", "vpc_id"),
+            ("ImageID", "image_id"),
+            ("CPU", "cpu"),
+            ("Ipv6Address", "ipv6_address"),
+            ("2FA", "_2_fa"),
+            ("instance-name", "instance_name"),
+            ("Type", "r#type"),
+            ("Self", "self_"),
+            ("", "_"),
+        ];
+
+        for (name, field) in cases {
+            assert_eq!(field_ident(name), field, "{name}");
+        }
+    }
+
+    #[test]
+    fn module_names() {
+        assert_eq!(service_module("Tke-Edge"), "tke_edge");
+        assert_eq!(version_module("2017-03-12"), "v20170312");
+    }
+
+    #[test]
+    fn render_fixture() {
+        let out = render(&fixture());
+
+        // the recursive object is boxed, the list doesn't need it
+        assert!(out.contains("pub parent: Option<Box<Node>>,"), "{out}");
+        assert!(out.contains("pub children: Option<Vec<Node>>,"), "{out}");
+
+        // the duplicated field is suffixed
+        assert!(out.contains("pub image_id: Option<String>,"), "{out}");
+        assert!(out.contains("pub image_id_: Option<String>,"), "{out}");
+
+        // only the api with the `ClientToken` is idempotent
+        assert_eq!(out.matches("IDEMPOTENCY_TOKEN_FIELD").count(), 1, "{out}");
+
+        // the request id is returned by the client, the code block is not a doc test
+        assert!(!out.contains("request_id"), "{out}");
+        assert!(out.contains("/// ```text"), "{out}");
+        assert_eq!(out.matches("#[deprecated]").count(), 1, "{out}");
+    }
+}
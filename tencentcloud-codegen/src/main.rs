@@ -0,0 +1,211 @@
+//! generate the tencentcloud service apis from the api specs
+//!
+//! usage: `tencentcloud-codegen [--crate-dir <dir>] <spec>...`
+//!
+//! the `<spec>` is an `api.json` file or a dir which contains the `api.json` files, every spec
+//! is generated into `src/services/<service>/v<version>.rs` of the tencentcloud crate, then the
+//! `mod` files and the `service-<service>` features are updated by the generated services
+
+use std::error::Error;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::{env, fs};
+
+use crate::spec::Spec;
+
+mod generate;
+mod spec;
+
+/// the begin marker of the service features in the `Cargo.toml`
+const FEATURES_BEGIN: &str = "# service features begin";
+
+/// the end marker of the service features in the `Cargo.toml`
+const FEATURES_END: &str = "# service features end";
+
+const SERVICES_DOC: &str = "\
+//! the generated service apis
+//!
+//! every service is gated behind the `service-<service>` feature, the modules are generated by
+//! the `tencentcloud-codegen` from the tencentcloud api specs
+";
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("error: {err}");
+
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let mut crate_dir = PathBuf::from(".");
+    let mut spec_paths = vec![];
+
+    let mut args = env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--crate-dir" {
+            crate_dir = args.next().ok_or("miss the --crate-dir value")?.into();
+        } else if arg == "-h" || arg == "--help" {
+            println!("usage: tencentcloud-codegen [--crate-dir <dir>] <spec>...");
+
+            return Ok(());
+        } else {
+            spec_paths.push(PathBuf::from(arg));
+        }
+    }
+
+    if spec_paths.is_empty() {
+        return Err("miss the api spec".into());
+    }
+
+    let mut specs = vec![];
+    for path in &spec_paths {
+        find_specs(path, &mut specs)?;
+    }
+
+    let services_dir = crate_dir.join("src").join("services");
+    for path in &specs {
+        let content = fs::read_to_string(path)
+            .map_err(|err| format!("read {} failed: {err}", path.display()))?;
+        let spec = serde_json::from_str::<Spec>(&content)
+            .map_err(|err| format!("parse {} failed: {err}", path.display()))?;
+
+        let service_dir =
+            services_dir.join(generate::service_module(&spec.metadata.service_short_name));
+        fs::create_dir_all(&service_dir)?;
+
+        let file = service_dir.join(format!(
+            "{}.rs",
+            generate::version_module(&spec.metadata.api_version)
+        ));
+        fs::write(&file, generate::render(&spec))?;
+        rustfmt(&file);
+
+        println!(
+            "generate {} {} to {}",
+            spec.metadata.service_short_name,
+            spec.metadata.api_version,
+            file.display()
+        );
+    }
+
+    let services = update_modules(&services_dir)?;
+    update_features(&crate_dir.join("Cargo.toml"), &services)?;
+
+    Ok(())
+}
+
+/// find the `api.json` files in the `path`
+fn find_specs(path: &Path, specs: &mut Vec<PathBuf>) -> Result<(), Box<dyn Error>> {
+    if !path.is_dir() {
+        specs.push(path.to_path_buf());
+
+        return Ok(());
+    }
+
+    let mut entries = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+
+    for entry in entries {
+        if entry.is_dir() {
+            find_specs(&entry, specs)?;
+        } else if entry.file_name() == Some(OsStr::new("api.json")) {
+            specs.push(entry);
+        }
+    }
+
+    Ok(())
+}
+
+/// write the `mod.rs` of the services and every service by the generated files, return the
+/// service module names
+fn update_modules(services_dir: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut services = vec![];
+    for entry in fs::read_dir(services_dir)? {
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+
+        let mut versions = fs::read_dir(&path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .filter(|path| path.extension() == Some(OsStr::new("rs")))
+            .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+            .filter(|version| version.starts_with('v'))
+            .collect::<Vec<_>>();
+        if versions.is_empty() {
+            continue;
+        }
+        versions.sort();
+
+        let service = path
+            .file_name()
+            .and_then(OsStr::to_str)
+            .ok_or_else(|| format!("invalid service dir {}", path.display()))?
+            .to_string();
+
+        let mut content = format!("//! the {service} apis\n\n");
+        for version in versions {
+            content.push_str(&format!("pub mod {version};\n"));
+        }
+        fs::write(path.join("mod.rs"), content)?;
+
+        services.push(service);
+    }
+    services.sort();
+
+    let mut content = format!("{SERVICES_DOC}\n");
+    for service in &services {
+        content.push_str(&format!(
+            "#[cfg(feature = \"{}\")]\npub mod {service};\n",
+            feature(service)
+        ));
+    }
+    fs::write(
+        services_dir.join("mod.rs"),
+        content.trim_end().to_string() + "\n",
+    )?;
+
+    Ok(services)
+}
+
+/// replace the service features between the markers in the `Cargo.toml`
+fn update_features(manifest: &Path, services: &[String]) -> Result<(), Box<dyn Error>> {
+    let content = fs::read_to_string(manifest)?;
+    let (head, rest) = content
+        .split_once(FEATURES_BEGIN)
+        .ok_or_else(|| format!("miss {FEATURES_BEGIN:?} in {}", manifest.display()))?;
+    let (_, tail) = rest
+        .split_once(FEATURES_END)
+        .ok_or_else(|| format!("miss {FEATURES_END:?} in {}", manifest.display()))?;
+
+    let mut features = String::new();
+    for service in services {
+        features.push_str(&format!("{} = []\n", feature(service)));
+    }
+
+    fs::write(
+        manifest,
+        format!("{head}{FEATURES_BEGIN}\n{features}{FEATURES_END}{tail}"),
+    )?;
+
+    Ok(())
+}
+
+/// the feature of the service module
+fn feature(service: &str) -> String {
+    format!("service-{}", service.replace('_', "-"))
+}
+
+/// format the generated file, it is fine if the rustfmt is not installed
+fn rustfmt(file: &Path) {
+    let _ = Command::new("rustfmt")
+        .args(["--edition", "2021"])
+        .arg(file)
+        .status();
+}
@@ -0,0 +1,82 @@
+//! the tencentcloud api spec, the `api.json` of a service version
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct Spec {
+    #[serde(rename = "metadata")]
+    pub metadata: Metadata,
+
+    #[serde(rename = "actions")]
+    pub actions: BTreeMap<String, Action>,
+
+    #[serde(rename = "objects")]
+    pub objects: BTreeMap<String, Object>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Metadata {
+    /// the api version, for example: `2017-03-12`
+    #[serde(rename = "apiVersion")]
+    pub api_version: String,
+
+    /// the service, for example: `cvm`
+    #[serde(rename = "serviceShortName")]
+    pub service_short_name: String,
+
+    /// the service display name
+    #[serde(rename = "serviceNameCN", default)]
+    pub service_name_cn: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Action {
+    #[serde(rename = "document", default)]
+    pub document: String,
+
+    /// the request object name
+    #[serde(rename = "input")]
+    pub input: String,
+
+    /// the response object name
+    #[serde(rename = "output")]
+    pub output: String,
+
+    #[serde(rename = "status", default)]
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Object {
+    #[serde(rename = "document", default)]
+    pub document: String,
+
+    #[serde(rename = "members", default)]
+    pub members: Vec<Member>,
+
+    /// `in`, `out` or `both`
+    #[serde(rename = "usage", default)]
+    pub usage: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Member {
+    #[serde(rename = "name")]
+    pub name: String,
+
+    #[serde(rename = "document", default)]
+    pub document: String,
+
+    /// the member type, the `list` and `object` use the [`Member::member`] as the element type
+    #[serde(rename = "type")]
+    pub ty: String,
+
+    /// the element type of the `list`, or the object name of the `object`
+    #[serde(rename = "member", default)]
+    pub member: String,
+
+    #[serde(rename = "required", default)]
+    pub required: bool,
+}
@@ -0,0 +1,128 @@
+//! generate the fixture spec into a scratch crate, then build it under the `service-demo`
+//! feature against the tencentcloud crate
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// the scratch crate, the generated code refers the `crate::api`
+const MANIFEST: &str = r#"[package]
+name = "codegen-fixture"
+version = "0.0.0"
+edition = "2021"
+publish = false
+
+[workspace]
+
+[dependencies]
+serde = { version = "1", features = ["derive"] }
+serde_json = "1"
+tencentcloud = { path = "{root}" }
+
+[features]
+# service features begin
+# service features end
+"#;
+
+/// the generated apis are checked at compile time
+const LIB: &str = r#"pub use tencentcloud::api;
+
+pub mod services;
+
+#[cfg(feature = "service-demo")]
+#[allow(dead_code, deprecated)]
+mod assertions {
+    use tencentcloud::api::Api;
+
+    use crate::services::demo::v20200101::*;
+
+    const _: () = assert!(CreateThing::IDEMPOTENCY_TOKEN_FIELD.is_some());
+    const _: () = assert!(DescribeThings::IDEMPOTENCY_TOKEN_FIELD.is_none());
+
+    fn fields() {
+        let node = Node {
+            name: Some("root".to_string()),
+            parent: None,
+            children: Some(vec![]),
+        };
+        let _ = CreateThingRequest {
+            vpc_id: String::new(),
+            r#type: String::new(),
+            image_id: None,
+            image_id_: None,
+            self_: Some(true),
+            _2_fa: None,
+            instance_name: None,
+            tags: Some(vec![Tag {
+                key: None,
+                value: None,
+            }]),
+            node: Some(Node {
+                name: None,
+                parent: Some(Box::new(node)),
+                children: None,
+            }),
+            extra: Some(serde_json::Value::Null),
+            client_token: None,
+        };
+        let _ = CreateThingResponse { thing_id: None };
+
+        let _ = DescribeThingsRequest {
+            offset: Some(0_u64),
+            limit: Some(10_i64),
+            filters: Some(vec![Filter {
+                name: String::new(),
+                values: vec![],
+            }]),
+        };
+        let _ = DescribeThingsResponse {
+            total_count: Some(0),
+            thing_set: None,
+        };
+    }
+}
+"#;
+
+#[test]
+fn generate_and_build_fixture() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+    let tmp = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    let crate_dir = tmp.join("codegen-fixture");
+
+    let _ = fs::remove_dir_all(&crate_dir);
+    fs::create_dir_all(crate_dir.join("src")).unwrap();
+    fs::write(
+        crate_dir.join("Cargo.toml"),
+        MANIFEST.replace("{root}", &root.display().to_string()),
+    )
+    .unwrap();
+    fs::write(crate_dir.join("src").join("lib.rs"), LIB).unwrap();
+    // reuse the locked versions of the workspace
+    fs::copy(root.join("Cargo.lock"), crate_dir.join("Cargo.lock")).unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_tencentcloud-codegen"))
+        .arg("--crate-dir")
+        .arg(&crate_dir)
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let manifest = fs::read_to_string(crate_dir.join("Cargo.toml")).unwrap();
+    assert!(manifest.contains("service-demo = []"), "{manifest}");
+    assert!(crate_dir.join("src/services/demo/v20200101.rs").is_file());
+
+    let output = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()))
+        .args(["check", "--features", "service-demo"])
+        .current_dir(&crate_dir)
+        .env("CARGO_TARGET_DIR", tmp.join("codegen-fixture-target"))
+        .env("RUSTFLAGS", "-D warnings")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
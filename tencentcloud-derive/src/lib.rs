@@ -0,0 +1,468 @@
+//! the derive macro of the tencentcloud api
+//!
+//! `#[derive(TencentApi)]` implements the `tencentcloud::api::Api` for the request struct, the
+//! request struct itself is the `Api`, and implements the `serde::Serialize` with
+//! `rename_all = "PascalCase"`
+//!
+//! ## Attributes:
+//!
+//! the struct attribute `#[tc(...)]`:
+//!
+//! - `service = "tmt"`: required, the api service
+//! - `version = "2018-03-21"`: required, the api version
+//! - `action = "TextTranslate"`: required, the api action
+//! - `response = TextTranslateResponse`: required, the api response type
+//! - `host = "tmt.tencentcloudapi.com"`: the api host, default is
+//!   `<service>.tencentcloudapi.com`
+//! - `scheme = "https"`, `path = "/"`: the api scheme and path
+//! - `skip_sign`: call the api without signing
+//! - `idempotent`: set the `IDEMPOTENCY_TOKEN_FIELD` to `ClientToken`
+//! - `rename_all = "PascalCase"`: the serde `rename_all` of the fields, default is `PascalCase`
+//! - `no_serialize`: don't implement the `Serialize`, when it is derived by the serde or
+//!   implemented by hand
+//!
+//! the `#[serde(...)]` field and struct attributes are kept, the `Option` field is skipped when it
+//! is `None` unless the field has its own `skip_serializing_if`; the struct `#[serde(rename_all =
+//! ...)]` replaces the default `PascalCase`, it can't be used with the `#[tc(rename_all = ...)]`,
+//! and the `#[serde(crate = ...)]` and `#[serde(remote = ...)]` are not supported
+//!
+//! ## Examples:
+//!
+//! ```rust
+//! use serde::Deserialize;
+//! use tencentcloud::TencentApi;
+//!
+//! #[derive(Debug, Clone, TencentApi)]
+//! #[tc(
+//!     service = "tmt",
+//!     version = "2018-03-21",
+//!     action = "TextTranslate",
+//!     response = TextTranslateResponse
+//! )]
+//! pub struct TextTranslateRequest {
+//!     pub source_text: String,
+//!     pub source: String,
+//!     pub target: String,
+//!     pub project_id: i64,
+//!     pub untranslated_text: Option<String>,
+//! }
+//!
+//! #[derive(Debug, Clone, Deserialize)]
+//! #[serde(rename_all = "PascalCase")]
+//! pub struct TextTranslateResponse {
+//!     pub source: String,
+//!     pub target: String,
+//!     pub target_text: String,
+//! }
+//!
+//! # use tencentcloud::api::Api;
+//! # assert_eq!(TextTranslateRequest::HOST, "tmt.tencentcloudapi.com");
+//! ```
+//!
+//! the `idempotent` api generates the `ClientToken` when the request doesn't set it:
+//!
+//! ```rust
+//! use serde::Deserialize;
+//! use tencentcloud::TencentApi;
+//!
+//! #[derive(Debug, Clone, TencentApi)]
+//! #[tc(
+//!     service = "cvm",
+//!     version = "2017-03-12",
+//!     action = "RunInstances",
+//!     response = RunInstancesResponse,
+//!     idempotent
+//! )]
+//! pub struct RunInstancesRequest {
+//!     pub instance_name: String,
+//!     pub client_token: Option<String>,
+//! }
+//!
+//! #[derive(Debug, Clone, Deserialize)]
+//! #[serde(rename_all = "PascalCase")]
+//! pub struct RunInstancesResponse {
+//!     pub instance_id_set: Vec<String>,
+//! }
+//!
+//! # use tencentcloud::api::Api;
+//! # assert_eq!(RunInstancesRequest::IDEMPOTENCY_TOKEN_FIELD, Some("ClientToken"));
+//! ```
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenTree;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, Attribute, Data, DataStruct, DeriveInput, Error, Fields, LitStr, Meta, Type,
+    TypePath,
+};
+
+/// implement the `tencentcloud::api::Api` and `serde::Serialize` for the request struct
+///
+/// see the [crate] document for the attributes
+#[proc_macro_derive(TencentApi, attributes(tc, serde))]
+pub fn derive_tencent_api(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// the struct attribute `#[tc(...)]`
+#[derive(Default)]
+struct ApiAttr {
+    service: Option<LitStr>,
+    version: Option<LitStr>,
+    action: Option<LitStr>,
+    response: Option<Type>,
+    host: Option<LitStr>,
+    scheme: Option<LitStr>,
+    path: Option<LitStr>,
+    skip_sign: bool,
+    idempotent: bool,
+    rename_all: Option<LitStr>,
+    no_serialize: bool,
+}
+
+impl ApiAttr {
+    fn parse(input: &DeriveInput) -> syn::Result<Self> {
+        let mut api_attr = Self::default();
+
+        for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("tc")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("service") {
+                    api_attr.service = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("version") {
+                    api_attr.version = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("action") {
+                    api_attr.action = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("response") {
+                    api_attr.response = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("host") {
+                    api_attr.host = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("scheme") {
+                    api_attr.scheme = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("path") {
+                    api_attr.path = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("skip_sign") {
+                    api_attr.skip_sign = true;
+                } else if meta.path.is_ident("idempotent") {
+                    api_attr.idempotent = true;
+                } else if meta.path.is_ident("rename_all") {
+                    api_attr.rename_all = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("no_serialize") {
+                    api_attr.no_serialize = true;
+                } else {
+                    return Err(meta.error("unknown tc attribute"));
+                }
+
+                Ok(())
+            })?;
+        }
+
+        Ok(api_attr)
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(
+            input.generics.span(),
+            "TencentApi doesn't support the generic struct",
+        ));
+    }
+
+    let fields = match &input.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(fields),
+            ..
+        }) => fields,
+        _ => {
+            return Err(Error::new(
+                input.ident.span(),
+                "TencentApi only supports the struct with named fields",
+            ))
+        }
+    };
+
+    for field in &fields.named {
+        if let Some(attr) = field.attrs.iter().find(|attr| attr.path().is_ident("tc")) {
+            return Err(Error::new(
+                attr.span(),
+                "tc attribute is only supported on the struct",
+            ));
+        }
+    }
+
+    let api_attr = ApiAttr::parse(&input)?;
+    let missing = |name: &str| {
+        Error::new(
+            input.ident.span(),
+            format!("miss `#[tc({name} = ...)]` attribute"),
+        )
+    };
+    let service = api_attr.service.clone().ok_or_else(|| missing("service"))?;
+    let version = api_attr.version.clone().ok_or_else(|| missing("version"))?;
+    let action = api_attr.action.clone().ok_or_else(|| missing("action"))?;
+    let response = api_attr
+        .response
+        .clone()
+        .ok_or_else(|| missing("response"))?;
+    let host = api_attr.host.clone().unwrap_or_else(|| {
+        LitStr::new(
+            &format!("{}.tencentcloudapi.com", service.value()),
+            service.span(),
+        )
+    });
+
+    let ident = &input.ident;
+    let scheme = api_attr
+        .scheme
+        .as_ref()
+        .map(|scheme| quote! { const SCHEME: &'static str = #scheme; });
+    let path = api_attr
+        .path
+        .as_ref()
+        .map(|path| quote! { const PATH: &'static str = #path; });
+    let skip_sign = api_attr
+        .skip_sign
+        .then(|| quote! { const SKIP_SIGN: bool = true; });
+    let idempotency_token_field = api_attr.idempotent.then(|| {
+        quote! { const IDEMPOTENCY_TOKEN_FIELD: ::core::option::Option<&'static str> = ::core::option::Option::Some("ClientToken"); }
+    });
+
+    let api_impl = quote! {
+        impl ::tencentcloud::api::Api for #ident {
+            type Request = Self;
+            type Response = #response;
+            const VERSION: &'static str = #version;
+            const ACTION: &'static str = #action;
+            const SERVICE: &'static str = #service;
+            const HOST: &'static str = #host;
+            #scheme
+            #path
+            #skip_sign
+            #idempotency_token_field
+        }
+    };
+
+    let serialize_impl = if api_attr.no_serialize {
+        None
+    } else {
+        Some(expand_serialize(&input, fields, &api_attr)?)
+    };
+
+    Ok(quote! {
+        #api_impl
+
+        #serialize_impl
+    })
+}
+
+/// implement the `Serialize` by the serde remote derive, so the field attributes apply to the
+/// field types as they are
+fn expand_serialize(
+    input: &DeriveInput,
+    fields: &syn::FieldsNamed,
+    api_attr: &ApiAttr,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let ident = &input.ident;
+    let remote_ident = format_ident!("__TencentApi{}", ident);
+    let remote = LitStr::new(&ident.to_string(), ident.span());
+
+    // the struct serde attributes are forwarded to the remote derive struct
+    let container_attrs = input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("serde"))
+        .collect::<Vec<_>>();
+    if let Some(attr) = find_attr_ident(&container_attrs, &["crate", "remote"]) {
+        return Err(Error::new(
+            attr.span(),
+            "serde `crate` and `remote` attributes are not supported by TencentApi",
+        ));
+    }
+    let serde_rename_all = find_attr_ident(&container_attrs, &["rename_all"]);
+    let rename_all = match (&api_attr.rename_all, serde_rename_all) {
+        (Some(_), Some(attr)) => {
+            return Err(Error::new(
+                attr.span(),
+                "serde `rename_all` conflicts with the `#[tc(rename_all = ...)]`",
+            ))
+        }
+        (Some(rename_all), None) => Some(quote! { rename_all = #rename_all, }),
+        (None, Some(_)) => None,
+        (None, None) => Some(quote! { rename_all = "PascalCase", }),
+    };
+    // the serialized struct name is the request struct name rather than the remote one
+    let rename = find_attr_ident(&container_attrs, &["rename"])
+        .is_none()
+        .then(|| quote! { rename = #remote, });
+
+    let fields = fields.named.iter().map(|field| {
+        let serde_attrs = field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("serde"))
+            .collect::<Vec<_>>();
+        let skip_none = (is_option(&field.ty)
+            && find_attr_ident(
+                &serde_attrs,
+                &["skip", "skip_serializing", "skip_serializing_if"],
+            )
+            .is_none())
+        .then(|| {
+            quote! { #[serde(skip_serializing_if = "::std::option::Option::is_none")] }
+        });
+        let field_ident = &field.ident;
+        let ty = &field.ty;
+
+        quote! {
+            #(#serde_attrs)*
+            #skip_none
+            #field_ident: #ty
+        }
+    });
+
+    Ok(quote! {
+        const _: () = {
+            #[derive(::tencentcloud::__private::serde::Serialize)]
+            #[serde(
+                crate = "::tencentcloud::__private::serde",
+                remote = #remote,
+                #rename
+                #rename_all
+            )]
+            #(#container_attrs)*
+            #[allow(dead_code, non_camel_case_types)]
+            struct #remote_ident {
+                #(#fields,)*
+            }
+
+            impl ::tencentcloud::__private::serde::Serialize for #ident {
+                fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+                where
+                    S: ::tencentcloud::__private::serde::Serializer,
+                {
+                    #remote_ident::serialize(self, serializer)
+                }
+            }
+        };
+    })
+}
+
+/// whether the `ty` is the `Option`
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(TypePath { qself: None, path }) => path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
+
+/// find the serde attribute which contains one of the `names` at the top level
+fn find_attr_ident<'a>(attrs: &[&'a Attribute], names: &[&str]) -> Option<&'a Attribute> {
+    attrs.iter().copied().find(|attr| match &attr.meta {
+        Meta::List(list) => list.tokens.clone().into_iter().any(|token| {
+            matches!(
+                token,
+                TokenTree::Ident(ident) if names.iter().any(|name| ident == name)
+            )
+        }),
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    fn expand_err(input: DeriveInput) -> String {
+        match expand(input) {
+            Ok(tokens) => panic!("expand should fail: {tokens}"),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn tc_attribute_errors() {
+        let err = expand_err(parse_quote! {
+            #[tc(version = "2018-03-21", action = "TextTranslate", response = Response)]
+            struct Request { source: String }
+        });
+        assert_eq!(err, "miss `#[tc(service = ...)]` attribute");
+
+        let err = expand_err(parse_quote! {
+            #[tc(service = "tmt", unknown = "value")]
+            struct Request { source: String }
+        });
+        assert_eq!(err, "unknown tc attribute");
+
+        let err = expand_err(parse_quote! {
+            #[tc(service = 1)]
+            struct Request { source: String }
+        });
+        assert!(err.contains("expected string literal"), "{err}");
+
+        let err = expand_err(parse_quote! {
+            #[tc(service = "tmt")]
+            struct Request {
+                #[tc(skip_sign)]
+                source: String,
+            }
+        });
+        assert_eq!(err, "tc attribute is only supported on the struct");
+
+        let err = expand_err(parse_quote! {
+            #[tc(service = "tmt")]
+            struct Request(String);
+        });
+        assert_eq!(err, "TencentApi only supports the struct with named fields");
+
+        let err = expand_err(parse_quote! {
+            #[tc(service = "tmt")]
+            struct Request<T> { source: T }
+        });
+        assert_eq!(err, "TencentApi doesn't support the generic struct");
+    }
+
+    #[test]
+    fn serde_container_attribute_errors() {
+        let err = expand_err(parse_quote! {
+            #[tc(service = "tmt", version = "v", action = "A", response = R, rename_all = "camelCase")]
+            #[serde(rename_all = "snake_case")]
+            struct Request { source: String }
+        });
+        assert!(err.contains("conflicts"), "{err}");
+
+        let err = expand_err(parse_quote! {
+            #[tc(service = "tmt", version = "v", action = "A", response = R)]
+            #[serde(crate = "other_serde")]
+            struct Request { source: String }
+        });
+        assert!(err.contains("not supported"), "{err}");
+    }
+
+    #[test]
+    fn no_serialize() {
+        let expand = |input: DeriveInput| expand(input).unwrap().to_string();
+
+        let tokens = expand(parse_quote! {
+            #[tc(service = "tmt", version = "v", action = "A", response = R, no_serialize)]
+            struct Request { source: String }
+        });
+        assert!(tokens.contains("Api for Request"), "{tokens}");
+        assert!(!tokens.contains("Serialize"), "{tokens}");
+
+        let tokens = expand(parse_quote! {
+            #[tc(service = "tmt", version = "v", action = "A", response = R)]
+            struct Request { source: String }
+        });
+        assert!(tokens.contains("Serialize for Request"), "{tokens}");
+    }
+}
@@ -0,0 +1,135 @@
+//! serialize the requests which derive the `TencentApi`
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tencentcloud::api::Api;
+use tencentcloud::TencentApi;
+
+#[derive(Debug, Deserialize)]
+pub struct EmptyResponse {}
+
+#[derive(Debug, Serialize)]
+pub struct Filter {
+    pub name: String,
+}
+
+#[derive(Debug, TencentApi)]
+#[tc(
+    service = "cvm",
+    version = "2017-03-12",
+    action = "DescribeInstances",
+    response = EmptyResponse,
+    idempotent
+)]
+pub struct DescribeInstancesRequest {
+    pub instance_ids: Vec<String>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub filters: Vec<Filter>,
+    #[serde(skip_serializing_if = "Option::is_some")]
+    pub marker: Option<String>,
+    #[serde(rename = "ClientToken")]
+    pub token: Option<String>,
+    #[serde(skip)]
+    pub internal: u8,
+}
+
+#[test]
+fn skip_none_and_rename_fields() {
+    let request = DescribeInstancesRequest {
+        instance_ids: vec!["ins-1".to_string()],
+        limit: Some(10),
+        offset: None,
+        filters: vec![],
+        marker: None,
+        token: Some("token".to_string()),
+        internal: 1,
+    };
+
+    assert_eq!(
+        serde_json::to_value(&request).unwrap(),
+        json!({
+            "InstanceIds": ["ins-1"],
+            "Limit": 10,
+            // the explicit skip_serializing_if is kept as is, the `None` is not skipped
+            "Marker": null,
+            "ClientToken": "token",
+        })
+    );
+    assert_eq!(
+        DescribeInstancesRequest::IDEMPOTENCY_TOKEN_FIELD,
+        Some("ClientToken")
+    );
+    assert_eq!(DescribeInstancesRequest::HOST, "cvm.tencentcloudapi.com");
+}
+
+#[derive(Debug, TencentApi)]
+#[tc(
+    service = "demo",
+    version = "2020-01-01",
+    action = "Create",
+    response = EmptyResponse,
+    host = "demo.internal.tencentcloudapi.com",
+    rename_all = "camelCase",
+    skip_sign
+)]
+pub struct CamelCaseRequest {
+    pub instance_name: String,
+    pub image_id: Option<String>,
+}
+
+#[derive(Debug, TencentApi)]
+#[tc(service = "demo", version = "2020-01-01", action = "Create", response = EmptyResponse)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE", tag = "Type")]
+pub struct SerdeContainerRequest {
+    pub instance_name: String,
+}
+
+#[test]
+fn rename_all() {
+    let request = CamelCaseRequest {
+        instance_name: "name".to_string(),
+        image_id: Some("img-1".to_string()),
+    };
+    assert_eq!(
+        serde_json::to_value(&request).unwrap(),
+        json!({ "instanceName": "name", "imageId": "img-1" })
+    );
+    assert_eq!(CamelCaseRequest::HOST, "demo.internal.tencentcloudapi.com");
+    const _: () = assert!(CamelCaseRequest::SKIP_SIGN);
+
+    // the struct serde attributes are forwarded
+    let request = SerdeContainerRequest {
+        instance_name: "name".to_string(),
+    };
+    assert_eq!(
+        serde_json::to_value(&request).unwrap(),
+        json!({ "Type": "SerdeContainerRequest", "INSTANCE_NAME": "name" })
+    );
+}
+
+#[derive(Debug, Serialize, TencentApi)]
+#[tc(
+    service = "demo",
+    version = "2020-01-01",
+    action = "Create",
+    response = EmptyResponse,
+    no_serialize
+)]
+pub struct ManualRequest {
+    pub instance_name: Option<String>,
+}
+
+#[test]
+fn no_serialize() {
+    let request = ManualRequest {
+        instance_name: None,
+    };
+
+    // the serde derive is used as it is
+    assert_eq!(
+        serde_json::to_value(&request).unwrap(),
+        json!({ "instance_name": null })
+    );
+}